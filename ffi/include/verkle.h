@@ -0,0 +1,128 @@
+/* Generated by cbindgen from verkle-ffi; do not edit. */
+
+#ifndef VERKLE_H
+#define VERKLE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Length of a serialized root commitment.
+#define VERKLE_COMMITMENT_LEN 48
+
+// Result of every call. `VERKLE_STATUS_OK` is the only success value.
+typedef enum VerkleStatus {
+  VERKLE_STATUS_OK = 0,
+  // A required pointer argument was NULL.
+  VERKLE_STATUS_NULL_POINTER = 1,
+  // The setup bytes are not a width-256 KZG setup.
+  VERKLE_STATUS_INVALID_SETUP = 2,
+  // The key is not stored, so there is no value or proof to return.
+  VERKLE_STATUS_NOT_FOUND = 3,
+  // The tree changed since its last `verkle_tree_commit`; commit before proving.
+  VERKLE_STATUS_UNCOMMITTED = 4,
+  // The proof or root bytes could not be decoded.
+  VERKLE_STATUS_INVALID_ENCODING = 5,
+  // The proof decoded but does not prove the key against the root.
+  VERKLE_STATUS_VERIFICATION_FAILED = 6,
+  // The library panicked; the handles passed to the call must not be used again.
+  VERKLE_STATUS_PANIC = 7,
+} VerkleStatus;
+
+// A verkle tree over a width-256 KZG setup.
+typedef struct VerkleTree VerkleTree;
+
+// A KZG setup, loaded from the bytes written by `KzgVc::to_bytes`.
+typedef struct VerkleVc VerkleVc;
+
+// A byte buffer allocated by this library. Release it with `verkle_buffer_free`.
+typedef struct VerkleBuffer {
+  uint8_t *data;
+  size_t len;
+} VerkleBuffer;
+
+// Loads a setup from `len` bytes at `srs` into `*out`.
+//
+// # Safety
+// `srs` must point to `len` readable bytes and `out` to a writable handle pointer.
+enum VerkleStatus verkle_vc_load(const uint8_t *srs, size_t len, struct VerkleVc **out);
+
+// Releases a setup loaded by `verkle_vc_load`.
+//
+// # Safety
+// `vc` must be NULL or a handle from `verkle_vc_load` that was not freed yet.
+void verkle_vc_free(struct VerkleVc *vc);
+
+// Creates an empty tree over a copy of `vc` into `*out`.
+//
+// # Safety
+// `vc` must be a live setup handle and `out` a writable handle pointer.
+enum VerkleStatus verkle_tree_new(const struct VerkleVc *vc, struct VerkleTree **out);
+
+// Releases a tree created by `verkle_tree_new`.
+//
+// # Safety
+// `tree` must be NULL or a handle from `verkle_tree_new` that was not freed yet.
+void verkle_tree_free(struct VerkleTree *tree);
+
+// Stores a copy of the `len` bytes at `value` under the 32-byte `key`.
+//
+// # Safety
+// `tree` must be a live tree handle, `key` must point to 32 readable bytes and `value` to `len`
+// readable bytes.
+enum VerkleStatus verkle_tree_insert(struct VerkleTree *tree,
+                                     const uint8_t *key,
+                                     const uint8_t *value,
+                                     size_t len);
+
+// Copies the value stored under `key` into `*out`, or returns `VERKLE_STATUS_NOT_FOUND`.
+//
+// # Safety
+// `tree` must be a live tree handle, `key` must point to 32 readable bytes and `out` to a
+// writable buffer.
+enum VerkleStatus verkle_tree_get(const struct VerkleTree *tree,
+                                  const uint8_t *key,
+                                  struct VerkleBuffer *out);
+
+// Commits the tree and writes its compressed root to the `VERKLE_COMMITMENT_LEN` bytes at
+// `root`.
+//
+// # Safety
+// `tree` must be a live tree handle and `root` must point to `VERKLE_COMMITMENT_LEN` writable
+// bytes.
+enum VerkleStatus verkle_tree_commit(struct VerkleTree *tree, uint8_t *root);
+
+// Writes a serialized proof of the value under `key` against the last committed root into
+// `*out`. The encoding is that of `CompactProof::to_bytes`.
+//
+// # Safety
+// `tree` must be a live tree handle, `key` must point to 32 readable bytes and `out` to a
+// writable buffer.
+enum VerkleStatus verkle_tree_prove(const struct VerkleTree *tree,
+                                    const uint8_t *key,
+                                    struct VerkleBuffer *out);
+
+// Verifies a proof from `verkle_tree_prove` of `key` against `root`, without a tree. On
+// success, if `value` is not NULL, the proven value is copied into `*value`.
+//
+// # Safety
+// `vc` must be a live setup handle, `root` must point to `VERKLE_COMMITMENT_LEN` readable
+// bytes, `key` to 32 readable bytes, `proof` to `len` readable bytes, and `value` must be NULL
+// or point to a writable buffer.
+enum VerkleStatus verkle_verify_proof(const struct VerkleVc *vc,
+                                      const uint8_t *root,
+                                      const uint8_t *key,
+                                      const uint8_t *proof,
+                                      size_t len,
+                                      struct VerkleBuffer *value);
+
+// Releases a buffer filled by this library and resets it to empty.
+//
+// # Safety
+// `buffer` must be NULL or point to an empty buffer or one filled by this library that was not
+// freed yet.
+void verkle_buffer_free(struct VerkleBuffer *buffer);
+
+#endif  /* VERKLE_H */
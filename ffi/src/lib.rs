@@ -0,0 +1,274 @@
+//! C interface to the `verkle` crate, for the default width-256 tree with the KZG backend and
+//! the Blake3 hasher. The C header is checked in as `include/verkle.h`; after changing the
+//! interface, regenerate it with `VERKLE_REGENERATE_HEADER=1 cargo test -p verkle-ffi --test header`.
+//!
+//! Ownership rules:
+//! - Every `verkle_*_new` or `verkle_*_load` handle is owned by the caller and released with the
+//!   matching `verkle_*_free`. Passing NULL to a free function does nothing.
+//! - A tree keeps its own copy of the setup, so the `VerkleVc` it was created from may be freed
+//!   first.
+//! - A `VerkleBuffer` filled by this library is owned by the caller and released with
+//!   `verkle_buffer_free`. Inputs are only borrowed for the duration of the call.
+//! - Handles are not thread safe: a tree must not be used from two threads at once.
+
+use std::{panic, ptr, slice};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use verkle::{
+    compact::{verify_compact_proof, CompactProof},
+    kzg::Commitment,
+    KzgVc, Value,
+};
+
+/// Length of a serialized root commitment.
+pub const VERKLE_COMMITMENT_LEN: usize = 48;
+
+/// Result of every call. `VERKLE_STATUS_OK` is the only success value.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerkleStatus {
+    Ok = 0,
+    /// A required pointer argument was NULL.
+    NullPointer = 1,
+    /// The setup bytes are not a width-256 KZG setup.
+    InvalidSetup = 2,
+    /// The key is not stored, so there is no value or proof to return.
+    NotFound = 3,
+    /// The tree changed since its last `verkle_tree_commit`; commit before proving.
+    Uncommitted = 4,
+    /// The proof or root bytes could not be decoded.
+    InvalidEncoding = 5,
+    /// The proof decoded but does not prove the key against the root.
+    VerificationFailed = 6,
+    /// The library panicked; the handles passed to the call must not be used again.
+    Panic = 7,
+}
+
+/// A byte buffer allocated by this library. Release it with `verkle_buffer_free`.
+#[repr(C)]
+pub struct VerkleBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// A KZG setup, loaded from the bytes written by `KzgVc::to_bytes`.
+pub struct VerkleVc(KzgVc<'static>);
+
+/// A verkle tree over a width-256 KZG setup.
+pub struct VerkleTree {
+    tree: verkle::VerkleTree<KzgVc<'static>>,
+}
+
+impl VerkleBuffer {
+    fn empty() -> Self {
+        VerkleBuffer { data: ptr::null_mut(), len: 0 }
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        VerkleBuffer { data: Box::into_raw(bytes.into_boxed_slice()).cast(), len }
+    }
+}
+
+// Runs `f`, turning a panic into `VerkleStatus::Panic` so it does not unwind into C.
+fn guard(f: impl FnOnce() -> VerkleStatus) -> VerkleStatus {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or(VerkleStatus::Panic)
+}
+
+// Borrows `len` bytes at `data`; a NULL `data` is only accepted for an empty slice.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    match data.is_null() {
+        true if len == 0 => Some(&[]),
+        true => None,
+        false => Some(slice::from_raw_parts(data, len)),
+    }
+}
+
+unsafe fn key(key: *const u8) -> Option<[u8; 32]> {
+    (!key.is_null()).then(|| *key.cast::<[u8; 32]>())
+}
+
+/// Loads a setup from `len` bytes at `srs` into `*out`.
+///
+/// # Safety
+/// `srs` must point to `len` readable bytes and `out` to a writable handle pointer.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_vc_load(srs: *const u8, len: usize, out: *mut *mut VerkleVc) -> VerkleStatus {
+    guard(|| {
+        let (Some(srs), false) = (bytes(srs, len), out.is_null()) else {
+            return VerkleStatus::NullPointer;
+        };
+        match KzgVc::from_bytes(srs) {
+            Some(vc) => {
+                *out = Box::into_raw(Box::new(VerkleVc(vc)));
+                VerkleStatus::Ok
+            }
+            None => VerkleStatus::InvalidSetup,
+        }
+    })
+}
+
+/// Releases a setup loaded by `verkle_vc_load`.
+///
+/// # Safety
+/// `vc` must be NULL or a handle from `verkle_vc_load` that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_vc_free(vc: *mut VerkleVc) {
+    if !vc.is_null() {
+        drop(Box::from_raw(vc));
+    }
+}
+
+/// Creates an empty tree over a copy of `vc` into `*out`.
+///
+/// # Safety
+/// `vc` must be a live setup handle and `out` a writable handle pointer.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_tree_new(vc: *const VerkleVc, out: *mut *mut VerkleTree) -> VerkleStatus {
+    guard(|| {
+        let (Some(vc), false) = (vc.as_ref(), out.is_null()) else {
+            return VerkleStatus::NullPointer;
+        };
+        *out = Box::into_raw(Box::new(VerkleTree { tree: verkle::VerkleTree::new(vc.0.clone()) }));
+        VerkleStatus::Ok
+    })
+}
+
+/// Releases a tree created by `verkle_tree_new`.
+///
+/// # Safety
+/// `tree` must be NULL or a handle from `verkle_tree_new` that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_tree_free(tree: *mut VerkleTree) {
+    if !tree.is_null() {
+        drop(Box::from_raw(tree));
+    }
+}
+
+/// Stores a copy of the `len` bytes at `value` under the 32-byte `key`.
+///
+/// # Safety
+/// `tree` must be a live tree handle, `key` must point to 32 readable bytes and `value` to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_tree_insert(tree: *mut VerkleTree, key: *const u8, value: *const u8, len: usize) -> VerkleStatus {
+    guard(|| {
+        let (Some(tree), Some(key), Some(value)) = (tree.as_mut(), self::key(key), bytes(value, len)) else {
+            return VerkleStatus::NullPointer;
+        };
+        tree.tree.insert(key, Value::from(value.to_vec()));
+        VerkleStatus::Ok
+    })
+}
+
+/// Copies the value stored under `key` into `*out`, or returns `VERKLE_STATUS_NOT_FOUND`.
+///
+/// # Safety
+/// `tree` must be a live tree handle, `key` must point to 32 readable bytes and `out` to a
+/// writable buffer.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_tree_get(tree: *const VerkleTree, key: *const u8, out: *mut VerkleBuffer) -> VerkleStatus {
+    guard(|| {
+        let (Some(tree), Some(key), false) = (tree.as_ref(), self::key(key), out.is_null()) else {
+            return VerkleStatus::NullPointer;
+        };
+        match tree.tree.get(key) {
+            Some(value) => {
+                *out = VerkleBuffer::from_vec(value.0.to_vec());
+                VerkleStatus::Ok
+            }
+            None => VerkleStatus::NotFound,
+        }
+    })
+}
+
+/// Commits the tree and writes its compressed root to the `VERKLE_COMMITMENT_LEN` bytes at
+/// `root`.
+///
+/// # Safety
+/// `tree` must be a live tree handle and `root` must point to `VERKLE_COMMITMENT_LEN` writable
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_tree_commit(tree: *mut VerkleTree, root: *mut u8) -> VerkleStatus {
+    guard(|| {
+        let (Some(tree), false) = (tree.as_mut(), root.is_null()) else {
+            return VerkleStatus::NullPointer;
+        };
+        let commitment = tree.tree.commit();
+        let root = slice::from_raw_parts_mut(root, VERKLE_COMMITMENT_LEN);
+        commitment.serialize_compressed(root).expect("serialize commitment");
+        VerkleStatus::Ok
+    })
+}
+
+/// Writes a serialized proof of the value under `key` against the last committed root into
+/// `*out`. The encoding is that of `CompactProof::to_bytes`.
+///
+/// # Safety
+/// `tree` must be a live tree handle, `key` must point to 32 readable bytes and `out` to a
+/// writable buffer.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_tree_prove(tree: *const VerkleTree, key: *const u8, out: *mut VerkleBuffer) -> VerkleStatus {
+    guard(|| {
+        let (Some(tree), Some(key), false) = (tree.as_ref(), self::key(key), out.is_null()) else {
+            return VerkleStatus::NullPointer;
+        };
+        if tree.tree.is_dirty() {
+            return VerkleStatus::Uncommitted;
+        }
+        match tree.tree.prove_get(key) {
+            Some(proof) => {
+                *out = VerkleBuffer::from_vec(proof.compact().to_bytes());
+                VerkleStatus::Ok
+            }
+            None => VerkleStatus::NotFound,
+        }
+    })
+}
+
+/// Verifies a proof from `verkle_tree_prove` of `key` against `root`, without a tree. On
+/// success, if `value` is not NULL, the proven value is copied into `*value`.
+///
+/// # Safety
+/// `vc` must be a live setup handle, `root` must point to `VERKLE_COMMITMENT_LEN` readable
+/// bytes, `key` to 32 readable bytes, `proof` to `len` readable bytes, and `value` must be NULL
+/// or point to a writable buffer.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_verify_proof(
+    vc: *const VerkleVc,
+    root: *const u8,
+    key: *const u8,
+    proof: *const u8,
+    len: usize,
+    value: *mut VerkleBuffer,
+) -> VerkleStatus {
+    guard(|| {
+        let (Some(vc), Some(root), Some(key), Some(proof)) = (vc.as_ref(), bytes(root, VERKLE_COMMITMENT_LEN), self::key(key), bytes(proof, len)) else {
+            return VerkleStatus::NullPointer;
+        };
+        let (Some(root), Some(proof)) = (Commitment::deserialize_compressed(root).ok(), CompactProof::<KzgVc>::from_bytes(proof)) else {
+            return VerkleStatus::InvalidEncoding;
+        };
+        if !verify_compact_proof(&vc.0, &root, &proof, key) {
+            return VerkleStatus::VerificationFailed;
+        }
+        if let Some(value) = value.as_mut() {
+            *value = VerkleBuffer::from_vec(proof.value.to_vec());
+        }
+        VerkleStatus::Ok
+    })
+}
+
+/// Releases a buffer filled by this library and resets it to empty.
+///
+/// # Safety
+/// `buffer` must be NULL or point to an empty buffer or one filled by this library that was not
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn verkle_buffer_free(buffer: *mut VerkleBuffer) {
+    let Some(buffer) = buffer.as_mut() else { return };
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)));
+    }
+    *buffer = VerkleBuffer::empty();
+}
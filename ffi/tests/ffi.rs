@@ -0,0 +1,106 @@
+use std::ptr;
+
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::KzgVc;
+use verkle_ffi::*;
+
+fn load_vc(rng: &mut StdRng) -> *mut VerkleVc {
+    let srs = <KzgVc>::setup(rng).expect("KZG setup should not fail").to_bytes();
+    let mut vc = ptr::null_mut();
+    assert_eq!(unsafe { verkle_vc_load(srs.as_ptr(), srs.len(), &mut vc) }, VerkleStatus::Ok);
+    vc
+}
+
+fn buffer() -> VerkleBuffer {
+    VerkleBuffer { data: ptr::null_mut(), len: 0 }
+}
+
+unsafe fn contents(buffer: &VerkleBuffer) -> Vec<u8> {
+    std::slice::from_raw_parts(buffer.data, buffer.len).to_vec()
+}
+
+#[test]
+fn tree_roundtrip_through_the_c_interface() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let vc = load_vc(&mut rng);
+    unsafe {
+        let mut tree = ptr::null_mut();
+        assert_eq!(verkle_tree_new(vc, &mut tree), VerkleStatus::Ok);
+        let mut sibling = [1u8; 32];
+        sibling[31] = 9;
+        let entries = [([1u8; 32], &b"one"[..]), ([2u8; 32], &b""[..]), (sibling, &b"sibling"[..])];
+        for (key, value) in &entries {
+            assert_eq!(verkle_tree_insert(tree, key.as_ptr(), value.as_ptr(), value.len()), VerkleStatus::Ok);
+        }
+
+        let mut out = buffer();
+        assert_eq!(verkle_tree_prove(tree, entries[0].0.as_ptr(), &mut out), VerkleStatus::Uncommitted);
+        let mut root = [0u8; VERKLE_COMMITMENT_LEN];
+        assert_eq!(verkle_tree_commit(tree, root.as_mut_ptr()), VerkleStatus::Ok);
+
+        // The setup handle is no longer needed by the tree
+        let verifier = load_vc(&mut StdRng::seed_from_u64(0xDEADBEEFCAFEBABE));
+        verkle_vc_free(vc);
+
+        for (key, value) in &entries {
+            assert_eq!(verkle_tree_get(tree, key.as_ptr(), &mut out), VerkleStatus::Ok);
+            assert_eq!(contents(&out), *value);
+            verkle_buffer_free(&mut out);
+            assert!(out.data.is_null());
+
+            let mut proof = buffer();
+            assert_eq!(verkle_tree_prove(tree, key.as_ptr(), &mut proof), VerkleStatus::Ok);
+            let mut proven = buffer();
+            assert_eq!(verkle_verify_proof(verifier, root.as_ptr(), key.as_ptr(), proof.data, proof.len, &mut proven), VerkleStatus::Ok);
+            assert_eq!(contents(&proven), *value);
+            verkle_buffer_free(&mut proven);
+
+            // Not a proof of another key
+            let other = [7u8; 32];
+            assert_eq!(verkle_verify_proof(verifier, root.as_ptr(), other.as_ptr(), proof.data, proof.len, ptr::null_mut()), VerkleStatus::VerificationFailed);
+            verkle_buffer_free(&mut proof);
+        }
+
+        verkle_tree_free(tree);
+        verkle_vc_free(verifier);
+    }
+}
+
+#[test]
+fn errors_are_reported_as_status_codes() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let vc = load_vc(&mut rng);
+    unsafe {
+        let mut handle = ptr::null_mut();
+        assert_eq!(verkle_vc_load(b"not a setup".as_ptr(), 11, &mut handle), VerkleStatus::InvalidSetup);
+        assert_eq!(verkle_vc_load(ptr::null(), 4, &mut handle), VerkleStatus::NullPointer);
+        assert!(handle.is_null());
+
+        let mut tree = ptr::null_mut();
+        assert_eq!(verkle_tree_new(ptr::null(), &mut tree), VerkleStatus::NullPointer);
+        assert_eq!(verkle_tree_new(vc, &mut tree), VerkleStatus::Ok);
+        let key = [3u8; 32];
+        let mut out = buffer();
+        assert_eq!(verkle_tree_insert(tree, ptr::null(), b"x".as_ptr(), 1), VerkleStatus::NullPointer);
+        assert_eq!(verkle_tree_get(tree, key.as_ptr(), &mut out), VerkleStatus::NotFound);
+        assert_eq!(verkle_tree_insert(tree, key.as_ptr(), ptr::null(), 0), VerkleStatus::Ok);
+
+        let mut root = [0u8; VERKLE_COMMITMENT_LEN];
+        assert_eq!(verkle_tree_commit(tree, root.as_mut_ptr()), VerkleStatus::Ok);
+        assert_eq!(verkle_tree_prove(tree, [4u8; 32].as_ptr(), &mut out), VerkleStatus::NotFound);
+        assert_eq!(verkle_tree_prove(tree, key.as_ptr(), &mut out), VerkleStatus::Ok);
+
+        let proof = contents(&out);
+        let truncated = &proof[..proof.len() - 1];
+        assert_eq!(verkle_verify_proof(vc, root.as_ptr(), key.as_ptr(), truncated.as_ptr(), truncated.len(), ptr::null_mut()), VerkleStatus::InvalidEncoding);
+        let bad_root = [0xFFu8; VERKLE_COMMITMENT_LEN];
+        assert_eq!(verkle_verify_proof(vc, bad_root.as_ptr(), key.as_ptr(), proof.as_ptr(), proof.len(), ptr::null_mut()), VerkleStatus::InvalidEncoding);
+        assert_eq!(verkle_verify_proof(vc, root.as_ptr(), key.as_ptr(), proof.as_ptr(), proof.len(), ptr::null_mut()), VerkleStatus::Ok);
+
+        verkle_buffer_free(&mut out);
+        verkle_buffer_free(ptr::null_mut());
+        verkle_tree_free(tree);
+        verkle_tree_free(ptr::null_mut());
+        verkle_vc_free(vc);
+    }
+}
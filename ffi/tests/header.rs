@@ -0,0 +1,27 @@
+use std::{env, fs};
+
+// The checked-in header must match the exported functions and types. Generating it is an explicit
+// step, so that builds never write into the source tree.
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = env!("CARGO_MANIFEST_DIR");
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).expect("cbindgen.toml");
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("generate C header")
+        .write(&mut generated);
+
+    let path = format!("{crate_dir}/include/verkle.h");
+    if env::var_os("VERKLE_REGENERATE_HEADER").is_some() {
+        fs::write(&path, &generated).expect("write header");
+        return;
+    }
+    let checked_in = fs::read(&path).expect("read header");
+    assert!(
+        checked_in == generated,
+        "include/verkle.h is out of date, regenerate it with `VERKLE_REGENERATE_HEADER=1 cargo test -p verkle-ffi --test header`"
+    );
+}
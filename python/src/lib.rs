@@ -0,0 +1,109 @@
+//! Python bindings to the `verkle` crate, built into an extension module named `verkle` by
+//! maturin from this directory.
+//!
+//! The module mirrors the C interface in `verkle-ffi`: a `KzgVc` setup, a `VerkleTree` over it
+//! with the default width and hasher, and proofs as `CompactProof` bytes that `verify_proof`
+//! checks without a tree. Keys are 32-byte `bytes` and roots are compressed commitments.
+//!
+//! Everything here is behind the `python` feature, which maturin enables from `pyproject.toml`.
+#![cfg(feature = "python")]
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+use verkle::{
+    compact::{verify_compact_proof, CompactProof},
+    kzg::Commitment,
+    KzgVc, Value, VerkleTree,
+};
+
+/// A KZG setup for width-256 trees.
+#[pyclass(name = "KzgVc", frozen)]
+struct PyKzgVc(KzgVc<'static>);
+
+/// A verkle tree over a width-256 KZG setup.
+#[pyclass(name = "VerkleTree")]
+struct PyVerkleTree {
+    tree: VerkleTree<KzgVc<'static>>,
+}
+
+fn key(key: &[u8]) -> PyResult<[u8; 32]> {
+    key.try_into().map_err(|_| PyValueError::new_err(format!("key must be 32 bytes, got {}", key.len())))
+}
+
+#[pymethods]
+impl PyKzgVc {
+    /// Runs a fresh setup. With a `seed` the setup is reproducible, and therefore insecure.
+    #[staticmethod]
+    #[pyo3(signature = (seed=None))]
+    fn setup(seed: Option<u64>) -> PyResult<Self> {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        KzgVc::setup(&mut rng).map(PyKzgVc).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Loads a setup written by `to_bytes`.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        KzgVc::from_bytes(data).map(PyKzgVc).ok_or_else(|| PyValueError::new_err("not a width-256 KZG setup"))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+}
+
+#[pymethods]
+impl PyVerkleTree {
+    #[new]
+    fn new(vc: &PyKzgVc) -> Self {
+        PyVerkleTree { tree: VerkleTree::new(vc.0.clone()) }
+    }
+
+    fn insert(&mut self, key: &[u8], value: &[u8]) -> PyResult<()> {
+        self.tree.insert(self::key(key)?, Value::from(value.to_vec()));
+        Ok(())
+    }
+
+    fn get(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
+        Ok(self.tree.get(self::key(key)?).map(|value| value.0.to_vec()))
+    }
+
+    /// Commits the tree and returns its compressed root.
+    fn commit(&mut self) -> Vec<u8> {
+        let mut root = Vec::new();
+        self.tree.commit().serialize_compressed(&mut root).expect("serialize commitment");
+        root
+    }
+
+    /// Returns the proof of the value under `key` against the last committed root, or None if the
+    /// key is not stored. Raises ValueError if the tree changed since then.
+    fn prove_get(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
+        if self.tree.is_dirty() {
+            return Err(PyValueError::new_err("the tree changed since the last commit"));
+        }
+        Ok(self.tree.prove_get(self::key(key)?).map(|proof| proof.compact().to_bytes()))
+    }
+}
+
+/// Checks a proof from `VerkleTree.prove_get` of `key` against `root`. Returns whether it holds;
+/// raises ValueError if `root` or `proof` cannot be decoded.
+#[pyfunction]
+fn verify_proof(vc: &PyKzgVc, root: &[u8], key: &[u8], proof: &[u8]) -> PyResult<bool> {
+    let root = Commitment::deserialize_compressed(root).map_err(|_| PyValueError::new_err("invalid root"))?;
+    let proof = CompactProof::<KzgVc>::from_bytes(proof).ok_or_else(|| PyValueError::new_err("invalid proof encoding"))?;
+    Ok(verify_compact_proof(&vc.0, &root, &proof, self::key(key)?))
+}
+
+/// The `verkle` module; named apart from the `verkle` crate it wraps.
+#[pymodule]
+#[pyo3(name = "verkle")]
+pub fn bindings(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyKzgVc>()?;
+    m.add_class::<PyVerkleTree>()?;
+    m.add_function(wrap_pyfunction!(verify_proof, m)?)?;
+    Ok(())
+}
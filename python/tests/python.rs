@@ -0,0 +1,69 @@
+use pyo3::{ffi::c_str, prelude::*, types::PyDict};
+
+// Runs `code` with the bindings imported as `verkle`.
+fn run(code: &std::ffi::CStr) {
+    Python::initialize();
+    Python::attach(|py| {
+        let globals = PyDict::new(py);
+        globals.set_item("verkle", pyo3::wrap_pymodule!(verkle_python::bindings)(py)).unwrap();
+        if let Err(e) = py.run(code, Some(&globals), None) {
+            e.print(py);
+            panic!("python code failed: {e}");
+        }
+    });
+}
+
+#[test]
+fn tree_and_proofs_from_python() {
+    run(c_str!(
+        r#"
+vc = verkle.KzgVc.setup(seed=7)
+assert verkle.KzgVc.setup(seed=7).to_bytes() == vc.to_bytes()
+tree = verkle.VerkleTree(vc)
+keys = [bytes([i % 3]) + bytes([i]) * 31 for i in range(20)]
+for i, key in enumerate(keys):
+    tree.insert(key, b"value %d" % i)
+assert tree.get(keys[4]) == b"value 4"
+assert tree.get(b"\xee" * 32) is None
+
+try:
+    tree.prove_get(keys[0])
+    raise AssertionError("proved an uncommitted tree")
+except ValueError:
+    pass
+root = tree.commit()
+assert len(root) == 48
+assert tree.prove_get(b"\xee" * 32) is None
+
+verifier = verkle.KzgVc.from_bytes(vc.to_bytes())
+for key in keys:
+    proof = tree.prove_get(key)
+    assert verkle.verify_proof(verifier, root, key, proof)
+    assert not verkle.verify_proof(verifier, root, keys[-1] if key != keys[-1] else keys[0], proof)
+"#
+    ));
+}
+
+#[test]
+fn malformed_inputs_raise_value_error() {
+    run(c_str!(
+        r#"
+def raises(f):
+    try:
+        f()
+    except ValueError:
+        return True
+    return False
+
+vc = verkle.KzgVc.setup(seed=7)
+tree = verkle.VerkleTree(vc)
+assert raises(lambda: tree.insert(b"short", b"x"))
+assert raises(lambda: verkle.KzgVc.from_bytes(b"not a setup"))
+tree.insert(b"\x01" * 32, b"x")
+root = tree.commit()
+proof = tree.prove_get(b"\x01" * 32)
+assert raises(lambda: verkle.verify_proof(vc, root, b"\x01" * 32, proof[:-1]))
+assert raises(lambda: verkle.verify_proof(vc, b"\xff" * 48, b"\x01" * 32, proof))
+"#
+    ));
+}
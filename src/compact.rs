@@ -0,0 +1,116 @@
+use alloc::{vec, vec::Vec};
+use core::marker::PhantomData;
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bytes::Bytes;
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    node::{split_key, stem_digit, stem_digits},
+    utils::digest_commit,
+    vc::{verify_proof, Step, VectorCommitment, VerkleProof, ARITY},
+};
+
+/// A [`VerkleProof`] without the fields a verifier can derive.
+///
+/// The root commitment is known to the verifier, each child digest is the digest of the next
+/// commitment, and each index is a digit of the key, so only the commitments below the root and
+/// one opening per step are kept. [`CompactProof::expand`] rebuilds the full proof, which is then
+/// checked as usual, so soundness is that of [`verify_proof`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactProof<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    pub commitments: Vec<V::Commitment>, // below the root, ending with the Extension's
+    pub proofs: Vec<V::Proof>,           // one opening per step, root first
+    pub value: Bytes,
+    pub(crate) hasher: PhantomData<H>,
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleProof<V, H, W> {
+    /// Drops the root commitment, the child digests and the indices.
+    pub fn compact(&self) -> CompactProof<V, H, W> {
+        let commitments = self
+            .steps
+            .iter()
+            .skip(1)
+            .map(|step| match step {
+                Step::Internal { parent_commit, .. } => parent_commit.clone(),
+                Step::Extension { ext_commit, .. } => ext_commit.clone(),
+            })
+            .collect();
+        let proofs = self
+            .steps
+            .iter()
+            .map(|step| match step {
+                Step::Internal { proof, .. } | Step::Extension { proof, .. } => proof.clone(),
+            })
+            .collect();
+        CompactProof { commitments, proofs, value: self.value.clone(), hasher: PhantomData }
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> CompactProof<V, H, W> {
+    /// Rebuilds the full proof of `key` against `root`. Returns None if the proof has more steps
+    /// than a path can, or not one opening per step.
+    pub fn expand(&self, root: &V::Commitment, key: [u8; 32]) -> Option<VerkleProof<V, H, W>> {
+        let (stem, suf) = split_key::<W>(key);
+        let depth = self.commitments.len();
+        if depth > stem_digits::<W>() || self.proofs.len() != depth + 1 {
+            return None;
+        }
+
+        let mut steps = Vec::with_capacity(depth + 1);
+        let mut parent = root;
+        for (level, (child, proof)) in self.commitments.iter().zip(&self.proofs).enumerate() {
+            steps.push(Step::Internal {
+                parent_commit: parent.clone(),
+                index: stem_digit::<W>(&stem, level),
+                child_digest: digest_commit::<V::Fr, H>(child),
+                proof: proof.clone(),
+            });
+            parent = child;
+        }
+        steps.push(Step::Extension { ext_commit: parent.clone(), index: suf as usize, proof: self.proofs[depth].clone() });
+
+        Some(VerkleProof { steps, value: self.value.clone(), hasher: PhantomData })
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> CompactProof<V, H, W>
+where
+    V::Commitment: CanonicalDeserialize,
+    V::Proof: CanonicalSerialize + CanonicalDeserialize,
+{
+    /// Encodes the proof as `number of commitments (u8) || commitments || openings || value
+    /// length (u32 LE) || value`, with commitments and openings compressed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.commitments.len() as u8];
+        for commit in &self.commitments {
+            commit.serialize_compressed(&mut out).expect("serialize commitment");
+        }
+        for proof in &self.proofs {
+            proof.serialize_compressed(&mut out).expect("serialize opening");
+        }
+        out.extend_from_slice(&(self.value.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.value);
+        out
+    }
+
+    /// Decodes [`CompactProof::to_bytes`]. Returns None unless `bytes` holds exactly one proof.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let (&depth, rest) = bytes.split_first()?;
+        bytes = rest;
+        let commitments = (0..depth).map(|_| V::Commitment::deserialize_compressed(&mut bytes).ok()).collect::<Option<Vec<_>>>()?;
+        let proofs = (0..=depth).map(|_| V::Proof::deserialize_compressed(&mut bytes).ok()).collect::<Option<Vec<_>>>()?;
+
+        let (len, value) = bytes.split_first_chunk::<4>()?;
+        if value.len() != u32::from_le_bytes(*len) as usize {
+            return None;
+        }
+        Some(CompactProof { commitments, proofs, value: Bytes::copy_from_slice(value), hasher: PhantomData })
+    }
+}
+
+/// Verifies a [`CompactProof`] of `key` against `root_commit`.
+pub fn verify_compact_proof<V: VectorCommitment<W>, H: TreeHasher, const W: usize>(vc: &V, root_commit: &V::Commitment, proof: &CompactProof<V, H, W>, key: [u8; 32]) -> bool {
+    proof.expand(root_commit, key).is_some_and(|full| verify_proof(vc, root_commit, &full, key))
+}
@@ -0,0 +1,105 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    node::{empty_digests, split_key, stem_digit, Node},
+    tree::{insert_at, lookup, VerkleTree},
+    utils::{digest_commit, ZERO_CHILD},
+    vc::{compute_commitment, VectorCommitment, ARITY},
+    Value,
+};
+
+/// A verkle tree that takes writes through `&self`, for use from many threads.
+///
+/// The subtree under each child of the root sits behind its own lock, so writers whose keys
+/// differ in the first stem digit do not wait for each other. Each shard holds exactly the
+/// subtree a [`VerkleTree`] with the same entries would hold under that child, so both give the
+/// same root.
+pub struct ConcurrentVerkleTree<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    shards: Vec<Mutex<Option<Node<V, W>>>>, // W shards indexed by the first stem digit, on the heap as nodes are large
+    vc: V,
+    hasher: PhantomData<H>,
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> ConcurrentVerkleTree<V, H, W> {
+    pub fn new(vc: V) -> Self {
+        const { assert!(W.is_power_of_two() && W >= 2 && W <= 256, "width must be a power of two in 2..=256") };
+        ConcurrentVerkleTree { shards: (0..W).map(|_| Mutex::new(None)).collect(), vc, hasher: PhantomData }
+    }
+
+    fn shard(&self, index: usize) -> MutexGuard<'_, Option<Node<V, W>>> {
+        self.shards[index].lock().expect("shard lock poisoned")
+    }
+
+    pub fn get(&self, key: [u8; 32]) -> Option<Value> {
+        let (stem, suf) = split_key::<W>(key);
+        lookup(self.shard(stem_digit::<W>(&stem, 0)).as_ref(), 1, &stem, suf)
+    }
+
+    /// Inserts `value` at `key`, locking only the shard of the key's first stem digit.
+    pub fn insert(&self, key: [u8; 32], value: Value) {
+        let (stem, suf) = split_key::<W>(key);
+        insert_at(&mut self.shard(stem_digit::<W>(&stem, 0)), 1, stem, suf, value);
+    }
+
+    /// Commits every shard in parallel, then the root over the shard digests. All shards are
+    /// locked for the duration, so the root reflects a single point between inserts.
+    pub fn commit(&self) -> V::Commitment
+    where
+        V: Sync,
+        V::Fr: Send,
+        V::Commitment: Send,
+    {
+        // Locks are always taken in index order, and inserts hold only one
+        let mut guards: Vec<_> = (0..W).map(|i| self.shard(i)).collect();
+        let mut occupied: Vec<(usize, &mut Node<V, W>)> = guards.iter_mut().enumerate().filter_map(|(i, g)| Some((i, g.as_mut()?))).collect();
+
+        // A single stem is stored as a lone Extension at the root, not below an Internal
+        if let [(_, node @ Node::Extension { .. })] = &mut occupied[..] {
+            return compute_commitment::<V, H, W>(&self.vc, node);
+        }
+        if occupied.is_empty() {
+            return V::Commitment::default();
+        }
+
+        let workers = thread::available_parallelism().map_or(1, |n| n.get());
+        let per_worker = occupied.len().div_ceil(workers);
+        let vc = &self.vc;
+        let commits: Vec<(usize, V::Commitment)> = thread::scope(|scope| {
+            let handles: Vec<_> = occupied
+                .chunks_mut(per_worker)
+                .map(|chunk| scope.spawn(move || chunk.iter_mut().map(|(i, node)| (*i, compute_commitment::<V, H, W>(vc, node))).collect::<Vec<_>>()))
+                .collect();
+            handles.into_iter().flat_map(|h| h.join().expect("shard commit panicked")).collect()
+        });
+
+        let mut digests: [V::Fr; W] = std::array::from_fn(|_| ZERO_CHILD::<V::Fr, H>());
+        for (i, commit) in commits {
+            digests[i] = digest_commit::<V::Fr, H>(&commit);
+        }
+        self.vc.commit_from_children(&digests)
+    }
+
+    /// Reassembles the shards into a single-threaded tree.
+    pub fn into_tree(self) -> VerkleTree<V, H, W> {
+        let mut shards: Vec<Option<Node<V, W>>> = self.shards.into_iter().map(|s| s.into_inner().expect("shard lock poisoned")).collect();
+        let mut tree = VerkleTree::new(self.vc);
+
+        let occupied: Vec<usize> = (0..W).filter(|&i| shards[i].is_some()).collect();
+        tree.root = match occupied[..] {
+            [] => None,
+            [i] if matches!(shards[i], Some(Node::Extension { .. })) => shards[i].take(),
+            _ => Some(Node::Internal {
+                children: std::array::from_fn(|i| shards[i].take().map(Arc::new)),
+                commitments: empty_digests::<V, W>(),
+            }),
+        };
+        // Shard digests may predate the last inserts, and the new root has none yet
+        tree.dirty = tree.root.is_some();
+        tree
+    }
+}
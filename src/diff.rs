@@ -0,0 +1,116 @@
+use std::{cmp::Ordering, fmt, iter::Peekable};
+
+use crate::{
+    hasher::TreeHasher,
+    node::{join_key, Node},
+    tree::{Iter, VerkleTree},
+    vc::VectorCommitment,
+    Value,
+};
+
+/// One changed key between two trees, see [`VerkleTree::diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added { key: [u8; 32], new: Value },
+    Removed { key: [u8; 32], old: Value },
+    Modified { key: [u8; 32], old: Value, new: Value },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffError {
+    /// A tree changed since its last `commit()`, so its cached digests cannot be trusted.
+    Uncommitted,
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::Uncommitted => write!(f, "tree changed since its last commit"),
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+impl Change {
+    pub fn key(&self) -> [u8; 32] {
+        match self {
+            Change::Added { key, .. } | Change::Removed { key, .. } | Change::Modified { key, .. } => *key,
+        }
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Changes that turn `self` into `other`, in key order.
+    ///
+    /// Subtrees whose cached child digests are equal on both sides are skipped, so the cost
+    /// follows the size of the change rather than the size of the trees. The cached digests are
+    /// only refreshed by `commit()`, so this returns [`DiffError::Uncommitted`] if either tree
+    /// changed since its last commit. Both trees need the same vector commitment setup for
+    /// anything to be skipped.
+    pub fn diff(&self, other: &Self) -> Result<Vec<Change>, DiffError> {
+        if self.dirty || other.dirty {
+            return Err(DiffError::Uncommitted);
+        }
+        let mut out = Vec::new();
+        diff_nodes(self.root.as_ref(), other.root.as_ref(), &mut out);
+        Ok(out)
+    }
+}
+
+fn diff_nodes<V: VectorCommitment<W>, const W: usize>(old: Option<&Node<V, W>>, new: Option<&Node<V, W>>, out: &mut Vec<Change>) {
+    match (old, new) {
+        (Some(Node::Internal { children: old_children, commitments: old_digests }), Some(Node::Internal { children: new_children, commitments: new_digests })) => {
+            for i in 0..W {
+                // Equal digests mean equal subtrees, including both being empty
+                if old_digests[i] != new_digests[i] {
+                    diff_nodes(old_children[i].as_deref(), new_children[i].as_deref(), out);
+                }
+            }
+        }
+        (Some(Node::Extension { stem: old_stem, slots: old_slots, slot_commitment: old_digests }), Some(Node::Extension { stem: new_stem, slots: new_slots, slot_commitment: new_digests })) if old_stem == new_stem => {
+            if old_digests == new_digests {
+                return;
+            }
+            for (suf, (a, b)) in old_slots.iter().zip(new_slots.iter()).enumerate() {
+                let key = join_key(old_stem, suf as u8);
+                match (a, b) {
+                    (Some(a), Some(b)) if a != b => out.push(Change::Modified { key, old: a.clone(), new: b.clone() }),
+                    (Some(a), None) => out.push(Change::Removed { key, old: a.clone() }),
+                    (None, Some(b)) => out.push(Change::Added { key, new: b.clone() }),
+                    _ => {}
+                }
+            }
+        }
+        // The shapes differ here, e.g. a lone Extension on one side was split on the other, so
+        // fall back to merging the entries of both subtrees
+        _ => merge(Iter::new(old).peekable(), Iter::new(new).peekable(), out),
+    }
+}
+
+fn merge<'a, V: VectorCommitment<W>, const W: usize>(mut old: Peekable<Iter<'a, V, W>>, mut new: Peekable<Iter<'a, V, W>>, out: &mut Vec<Change>) {
+    loop {
+        let order = match (old.peek(), new.peek()) {
+            (None, None) => return,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((a, _)), Some((b, _))) => a.cmp(b),
+        };
+        match order {
+            Ordering::Less => {
+                let (key, v) = old.next().expect("peeked");
+                out.push(Change::Removed { key, old: v.clone() });
+            }
+            Ordering::Greater => {
+                let (key, v) = new.next().expect("peeked");
+                out.push(Change::Added { key, new: v.clone() });
+            }
+            Ordering::Equal => {
+                let ((key, a), (_, b)) = (old.next().expect("peeked"), new.next().expect("peeked"));
+                if a != b {
+                    out.push(Change::Modified { key, old: a.clone(), new: b.clone() });
+                }
+            }
+        }
+    }
+}
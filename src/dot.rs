@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use ark_serialize::CanonicalSerialize;
+
+use crate::{
+    hasher::TreeHasher,
+    node::{split_key, stem_digit, Node, Stem},
+    tree::VerkleTree,
+    utils::encode_hex,
+    vc::VectorCommitment,
+};
+
+/// What [`VerkleTree::to_dot`] draws besides the shape.
+#[derive(Clone, Debug, Default)]
+pub struct DotOptions {
+    /// Label nodes and slots with the first bytes of their cached digests. The digests are only
+    /// current after `commit()`.
+    pub digests: bool,
+    /// Highlight the nodes and edges a lookup of this key walks through.
+    pub highlight: Option<[u8; 32]>,
+}
+
+// Shown length of digests and values, in bytes.
+const SHORT: usize = 4;
+
+fn short(bytes: &[u8]) -> String {
+    if bytes.len() <= SHORT {
+        encode_hex(bytes)
+    } else {
+        format!("{}..", encode_hex(&bytes[..SHORT]))
+    }
+}
+
+fn short_digest(digest: &impl CanonicalSerialize) -> String {
+    let mut bytes = Vec::new();
+    digest.serialize_compressed(&mut bytes).expect("serialize digest");
+    short(&bytes)
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Renders the tree in Graphviz DOT: Internal nodes with their depth, edges labelled with
+    /// the child index, and Extensions with their stem and occupied slots.
+    pub fn to_dot(&self, opts: &DotOptions) -> String {
+        let mut out = String::from("digraph verkle {\n  node [shape=box, fontname=monospace];\n");
+        if let Some(root) = &self.root {
+            let mut next_id = 0;
+            let highlight = opts.highlight.map(|key| split_key::<W>(key).0);
+            write_node(root, 0, None, highlight.as_ref(), opts, &mut next_id, &mut out);
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+// Writes `node` and its subtree and returns its id. `digest` is the node's digest as cached in
+// its parent, and `highlight` the stem whose path is still being followed, if any.
+fn write_node<V: VectorCommitment<W>, const W: usize>(
+    node: &Node<V, W>,
+    depth: usize,
+    digest: Option<&V::Fr>,
+    highlight: Option<&Stem>,
+    opts: &DotOptions,
+    next_id: &mut usize,
+    out: &mut String,
+) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+    let color = if highlight.is_some() { ", color=red, penwidth=2" } else { "" };
+    let digest_line = match digest {
+        Some(d) if opts.digests => format!("\\ndigest {}", short_digest(d)),
+        _ => String::new(),
+    };
+
+    match node {
+        Node::Internal { children, commitments } => {
+            writeln!(out, "  n{id} [label=\"internal\\ndepth {depth}{digest_line}\"{color}];").unwrap();
+            let on_path = highlight.map(|stem| stem_digit::<W>(stem, depth));
+            for (index, child) in children.iter().enumerate() {
+                let Some(child) = child else { continue };
+                let follow = highlight.filter(|_| on_path == Some(index));
+                let child_id = write_node(child, depth + 1, Some(&commitments[index]), follow, opts, next_id, out);
+                let edge_color = if follow.is_some() { ", color=red, penwidth=2" } else { "" };
+                writeln!(out, "  n{id} -> n{child_id} [label=\"{index:#04x}\"{edge_color}];").unwrap();
+            }
+        }
+        Node::Extension { stem, slots, slot_commitment } => {
+            // Only the key's own Extension is drawn as reached. A missing key is not highlighted:
+            // its lookup stops at another stem's Extension, drawn in orange, which proves nothing.
+            let color = if highlight.is_some_and(|h| h != stem) { ", color=orange, penwidth=2" } else { color };
+            let mut label = format!("extension\\nstem {}{digest_line}", encode_hex(stem));
+            for (suffix, slot) in slots.iter().enumerate() {
+                let Some(value) = slot else { continue };
+                write!(label, "\\l{suffix:#04x}: {}", short(&value.0)).unwrap();
+                if opts.digests {
+                    write!(label, " ({})", short_digest(&slot_commitment[suffix])).unwrap();
+                }
+            }
+            writeln!(out, "  n{id} [label=\"{label}\\l\"{color}];").unwrap();
+        }
+    }
+    id
+}
@@ -0,0 +1,358 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use memmap2::Mmap;
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    node::{split_key, stem_digit, stem_digits, stem_len, Node, Stem},
+    tree::VerkleTree,
+    vc::{cached_commitment, Step, VectorCommitment, VerkleProof, ARITY},
+    Value,
+};
+
+const MAGIC: &[u8; 4] = b"VRKM";
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 32;
+const INTERNAL: u8 = 0;
+const EXTENSION: u8 = 1;
+const SLOT_LEN: usize = 12; // value offset (u64 LE) and length (u32 LE)
+
+#[derive(Debug)]
+pub enum FlatError {
+    Io(io::Error),
+    /// The input is not a flat tree, or is truncated.
+    Format(&'static str),
+    /// The file was written with another format version, width, hash function or commitment
+    /// scheme.
+    Incompatible(&'static str),
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlatError::Io(e) => write!(f, "flat tree access failed: {e}"),
+            FlatError::Format(reason) => write!(f, "invalid flat tree: {reason}"),
+            FlatError::Incompatible(what) => write!(f, "flat tree has a different {what}"),
+        }
+    }
+}
+
+impl std::error::Error for FlatError {}
+
+impl From<io::Error> for FlatError {
+    fn from(e: io::Error) -> Self {
+        FlatError::Io(e)
+    }
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> usize {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().expect("8 bytes")) as usize
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> usize {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().expect("4 bytes")) as usize
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> usize {
+    u16::from_le_bytes(bytes[at..at + 2].try_into().expect("2 bytes")) as usize
+}
+
+// Compressed sizes of a digest and a commitment, which the layout assumes to be fixed.
+fn sizes<V: VectorCommitment<W>, const W: usize>() -> (usize, usize) {
+    (V::Fr::default().compressed_size(), V::Commitment::default().compressed_size())
+}
+
+fn push_compressed(out: &mut Vec<u8>, item: &impl CanonicalSerialize, len: usize) {
+    let start = out.len();
+    item.serialize_compressed(&mut *out).expect("serialize");
+    assert_eq!(out.len() - start, len, "compressed size is not fixed");
+}
+
+// Length of the record of `node` itself, without its children and values.
+fn record_len<V: VectorCommitment<W>, const W: usize>(node: &Node<V, W>) -> usize {
+    let (digest_len, commit_len) = sizes::<V, W>();
+    match node {
+        Node::Internal { .. } => 1 + commit_len + W * (digest_len + 8),
+        Node::Extension { .. } => 1 + commit_len + stem_len::<W>() + W * (digest_len + SLOT_LEN),
+    }
+}
+
+// Length of `node` and its subtree as written by `write_node`.
+fn subtree_len<V: VectorCommitment<W>, const W: usize>(node: &Node<V, W>) -> usize {
+    let below: usize = match node {
+        Node::Internal { children, .. } => children.iter().flatten().map(|c| subtree_len(c)).sum(),
+        Node::Extension { slots, .. } => slots.iter().flatten().map(|v| v.0.len()).sum(),
+    };
+    below + record_len(node)
+}
+
+// Writes `node` and its subtree at offset `*pos` of the file, children and values first, and
+// returns the node's offset.
+fn write_node<V: VectorCommitment<W>, const W: usize>(vc: &V, node: &Node<V, W>, out: &mut impl Write, pos: &mut usize) -> io::Result<usize> {
+    let (digest_len, commit_len) = sizes::<V, W>();
+    let mut record = Vec::with_capacity(record_len(node));
+    let commit = cached_commitment(vc, node);
+
+    match node {
+        Node::Internal { children, commitments } => {
+            let mut offsets = Vec::with_capacity(W);
+            for child in children {
+                offsets.push(match child {
+                    Some(c) => write_node(vc, c, out, pos)?,
+                    None => 0,
+                });
+            }
+            record.push(INTERNAL);
+            push_compressed(&mut record, &commit, commit_len);
+            for digest in commitments {
+                push_compressed(&mut record, digest, digest_len);
+            }
+            for offset in offsets {
+                record.extend_from_slice(&(offset as u64).to_le_bytes());
+            }
+        }
+        Node::Extension { stem, slots, slot_commitment } => {
+            let mut values = Vec::new();
+            for slot in slots {
+                match slot {
+                    Some(value) => {
+                        values.push((*pos, value.0.len()));
+                        out.write_all(&value.0)?;
+                        *pos += value.0.len();
+                    }
+                    // Offset 0 is the header, so it marks an empty slot
+                    None => values.push((0, 0)),
+                }
+            }
+            record.push(EXTENSION);
+            push_compressed(&mut record, &commit, commit_len);
+            record.extend_from_slice(&stem[..stem_len::<W>()]);
+            for digest in slot_commitment {
+                push_compressed(&mut record, digest, digest_len);
+            }
+            for (offset, len) in values {
+                record.extend_from_slice(&(offset as u64).to_le_bytes());
+                record.extend_from_slice(&(len as u32).to_le_bytes());
+            }
+        }
+    }
+    let offset = *pos;
+    out.write_all(&record)?;
+    *pos += record.len();
+    Ok(offset)
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Commits the tree and writes it in the flat layout read by [`FlatTree`].
+    ///
+    /// The file starts with a header: `"VRKM" || format version || hasher ID || scheme version
+    /// || 0 || width (u16 LE) || digest size (u16 LE) || commitment size (u16 LE) || 0 0 ||
+    /// root offset (u64 LE) || file length (u64 LE)`. Nodes follow in post-order, each after the
+    /// nodes and values it points to:
+    /// - Internal: `0x00 || commitment || W digests || W child offsets (u64 LE)`
+    /// - Extension: `0x01 || commitment || stem || W digests || W x (value offset (u64 LE) ||
+    ///   value length (u32 LE))`
+    ///
+    /// A stem is stored without the bytes that only hold suffix bits, so a width-256 stem takes
+    /// 31 bytes. Offsets are from the start of the file and 0 marks an empty child or slot, or
+    /// an empty tree in the header. Commitments and digests are compressed.
+    ///
+    /// Nodes are streamed to `writer` as they are laid out; the header is sized up front, so the
+    /// file is never held in memory.
+    pub fn write_flat(&mut self, writer: impl Write) -> io::Result<V::Commitment> {
+        let root = self.commit();
+        let (digest_len, commit_len) = sizes::<V, W>();
+
+        // The root is written last, so it ends the file
+        let len = HEADER_LEN + self.root.as_ref().map_or(0, subtree_len);
+        let root_offset = self.root.as_ref().map_or(0, |node| len - record_len(node));
+
+        let mut header = [0u8; HEADER_LEN];
+        header[..4].copy_from_slice(MAGIC);
+        header[4..8].copy_from_slice(&[FORMAT_VERSION, H::ID, H::SCHEME_VERSION, 0]);
+        header[8..10].copy_from_slice(&(W as u16).to_le_bytes());
+        header[10..12].copy_from_slice(&(digest_len as u16).to_le_bytes());
+        header[12..14].copy_from_slice(&(commit_len as u16).to_le_bytes());
+        header[16..24].copy_from_slice(&(root_offset as u64).to_le_bytes());
+        header[24..32].copy_from_slice(&(len as u64).to_le_bytes());
+
+        let mut out = BufWriter::new(writer);
+        out.write_all(&header)?;
+        let mut pos = HEADER_LEN;
+        if let Some(node) = &self.root {
+            let offset = write_node(&self.vc, node, &mut out, &mut pos)?;
+            debug_assert_eq!((offset, pos), (root_offset, len), "sizing pass disagrees with the layout");
+        }
+        out.flush()?;
+        Ok(root)
+    }
+}
+
+/// A committed tree read in place from the layout written by [`VerkleTree::write_flat`], e.g.
+/// from a memory-mapped file.
+///
+/// Nodes are reached by offset and only the digests and commitments on a proof path are
+/// deserialized, so opening is constant time and `get` and `prove_get` touch a handful of pages.
+/// Proofs are equal to those of the tree the file was written from. The bytes are trusted to be
+/// an intact file from `write_flat`; damaged files may panic on access.
+pub struct FlatTree<B: AsRef<[u8]>, V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    bytes: B,
+    vc: V,
+    root: usize, // offset of the root node, 0 if the tree is empty
+    hasher: PhantomData<H>,
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> FlatTree<Mmap, V, H, W>
+where
+    V::Commitment: CanonicalDeserialize,
+{
+    /// Maps the file at `path` read-only. The file must not be modified while it is mapped.
+    pub fn open(path: impl AsRef<Path>, vc: V) -> Result<Self, FlatError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only, and files written by `write_flat` are not modified
+        // afterwards; truncating one while it is served is the caller's error.
+        let bytes = unsafe { Mmap::map(&file)? };
+        Self::new(bytes, vc)
+    }
+}
+
+impl<B: AsRef<[u8]>, V: VectorCommitment<W>, H: TreeHasher, const W: usize> FlatTree<B, V, H, W>
+where
+    V::Commitment: CanonicalDeserialize,
+{
+    /// Checks the header of `bytes`; nodes are only read on access.
+    pub fn new(bytes: B, vc: V) -> Result<Self, FlatError> {
+        let b = bytes.as_ref();
+        if b.len() < HEADER_LEN || &b[..4] != MAGIC {
+            return Err(FlatError::Format("missing flat tree magic"));
+        }
+        if b[4] != FORMAT_VERSION {
+            return Err(FlatError::Incompatible("format version"));
+        }
+        if b[5] != H::ID || b[6] != H::SCHEME_VERSION {
+            return Err(FlatError::Incompatible("hash function"));
+        }
+        if u16_at(b, 8) != W {
+            return Err(FlatError::Incompatible("width"));
+        }
+        if (u16_at(b, 10), u16_at(b, 12)) != sizes::<V, W>() {
+            return Err(FlatError::Incompatible("commitment scheme"));
+        }
+        if u64_at(b, 24) != b.len() {
+            return Err(FlatError::Format("file length does not match the header"));
+        }
+        let root = u64_at(b, 16);
+        if root >= b.len() {
+            return Err(FlatError::Format("root offset is out of range"));
+        }
+        Ok(FlatTree { root, bytes, vc, hasher: PhantomData })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    fn commitment_at(&self, node: usize) -> V::Commitment {
+        let (_, commit_len) = sizes::<V, W>();
+        V::Commitment::deserialize_compressed(&self.bytes()[node + 1..node + 1 + commit_len]).expect("stored commitment")
+    }
+
+    // Offset of the W digests of the node at `node`.
+    fn digests_offset(&self, node: usize) -> usize {
+        let (_, commit_len) = sizes::<V, W>();
+        match self.bytes()[node] {
+            INTERNAL => node + 1 + commit_len,
+            EXTENSION => node + 1 + commit_len + stem_len::<W>(),
+            _ => panic!("unknown node tag at offset {node}"),
+        }
+    }
+
+    fn digests_at(&self, node: usize) -> [V::Fr; W] {
+        let (digest_len, _) = sizes::<V, W>();
+        let start = self.digests_offset(node);
+        std::array::from_fn(|i| {
+            let at = start + i * digest_len;
+            V::Fr::deserialize_compressed(&self.bytes()[at..at + digest_len]).expect("stored digest")
+        })
+    }
+
+    // Offset of the table after the digests: child offsets or value slots.
+    fn table_offset(&self, node: usize) -> usize {
+        let (digest_len, _) = sizes::<V, W>();
+        self.digests_offset(node) + W * digest_len
+    }
+
+    fn stem_at(&self, node: usize) -> &[u8] {
+        let (_, commit_len) = sizes::<V, W>();
+        &self.bytes()[node + 1 + commit_len..node + 1 + commit_len + stem_len::<W>()]
+    }
+
+    fn value_at(&self, ext: usize, suf: usize) -> Option<Value> {
+        let at = self.table_offset(ext) + suf * SLOT_LEN;
+        let offset = u64_at(self.bytes(), at);
+        let len = u32_at(self.bytes(), at + 8);
+        (offset != 0).then(|| Value::from(self.bytes()[offset..offset + len].to_vec()))
+    }
+
+    /// The root the file was written at.
+    pub fn root(&self) -> V::Commitment {
+        if self.root == 0 {
+            return V::Commitment::default();
+        }
+        self.commitment_at(self.root)
+    }
+
+    // Offsets of the nodes from the root down to the Extension holding `stem`.
+    fn stem_path(&self, stem: &Stem) -> Option<Vec<usize>> {
+        let mut node = self.root;
+        let mut path = Vec::new();
+
+        for level in 0..=stem_digits::<W>() {
+            if node == 0 {
+                return None;
+            }
+            path.push(node);
+            match self.bytes()[node] {
+                INTERNAL if level < stem_digits::<W>() => {
+                    let index = stem_digit::<W>(stem, level);
+                    node = u64_at(self.bytes(), self.table_offset(node) + index * 8);
+                }
+                EXTENSION => return (self.stem_at(node) == &stem[..stem_len::<W>()]).then_some(path),
+                _ => break,
+            }
+        }
+        unreachable!("unexpected node at full stem depth")
+    }
+
+    pub fn get(&self, key: [u8; 32]) -> Option<Value> {
+        let (stem, suf) = split_key::<W>(key);
+        let ext = *self.stem_path(&stem)?.last().expect("path ends at an Extension");
+        self.value_at(ext, suf as usize)
+    }
+
+    /// Same as [`VerkleTree::prove_get`] on the tree the file was written from.
+    pub fn prove_get(&self, key: [u8; 32]) -> Option<VerkleProof<V, H, W>> {
+        let (stem, suf) = split_key::<W>(key);
+        let path = self.stem_path(&stem)?;
+        let (&ext, internals) = path.split_last().expect("path ends at an Extension");
+        let value = self.value_at(ext, suf as usize)?.0;
+
+        let mut steps = Vec::with_capacity(path.len());
+        for (level, &node) in internals.iter().enumerate() {
+            let index = stem_digit::<W>(&stem, level);
+            let (child_digest, proof) = self.vc.open_at(&self.digests_at(node), index);
+            steps.push(Step::Internal { parent_commit: self.commitment_at(node), index, child_digest, proof });
+        }
+        let (_, proof) = self.vc.open_at(&self.digests_at(ext), suf as usize);
+        steps.push(Step::Extension { ext_commit: self.commitment_at(ext), index: suf as usize, proof });
+
+        Some(VerkleProof { steps, value, hasher: PhantomData })
+    }
+}
@@ -0,0 +1,217 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use ark_crypto_primitives::sponge::{
+    poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge},
+    CryptographicSponge, FieldBasedCryptographicSponge,
+};
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha256};
+
+/// Role an input plays when it is hashed into the tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashDomain {
+    /// Compressed commitment of a child of an Internal node.
+    InternalChild,
+    /// `stem || suffix || value` of an occupied Extension slot.
+    ExtensionSlot,
+    /// Placeholder for an absent child of an Internal node.
+    EmptyChild,
+    /// Placeholder for an absent Extension slot.
+    EmptySlot,
+}
+
+/// Hash function used for child digests, slot digests and `hash_to_field`.
+///
+/// The hasher is selected per tree as a type parameter, so proofs produced by a tree can only be
+/// verified with the same hasher and commitment scheme.
+pub trait TreeHasher {
+    /// Identifier recorded wherever the hasher has to be named outside the type system.
+    const ID: u8;
+
+    /// Version of the commitment scheme, i.e. of how digests are framed before hashing.
+    /// Version 0 hashes every role without a tag; see [`DomainSeparated`] for version 1.
+    const SCHEME_VERSION: u8 = 0;
+
+    /// Bytes prepended to an input hashed in `domain`.
+    fn domain_prefix(_domain: HashDomain) -> &'static [u8] {
+        &[]
+    }
+
+    /// 32-byte digest of `input`.
+    fn hash(input: &[u8]) -> [u8; 32];
+
+    /// 64-byte digest of `input`, for reductions into a field with negligible bias.
+    fn hash_wide(input: &[u8]) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        for (i, half) in out.chunks_exact_mut(32).enumerate() {
+            let mut tagged = Vec::with_capacity(1 + input.len());
+            tagged.push(i as u8);
+            tagged.extend_from_slice(input);
+            half.copy_from_slice(&Self::hash(&tagged));
+        }
+        out
+    }
+
+    /// Maps `input` to a field element. Reduces the 32-byte digest by default, which is slightly
+    /// biased for fields smaller than 2^256; see [`Wide`] for a uniform alternative.
+    fn hash_to_field<F: PrimeField>(input: &[u8]) -> F {
+        F::from_le_bytes_mod_order(&Self::hash(input))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Blake3Hasher;
+
+impl TreeHasher for Blake3Hasher {
+    const ID: u8 = 0;
+
+    fn hash(input: &[u8]) -> [u8; 32] {
+        *blake3::hash(input).as_bytes()
+    }
+
+    fn hash_wide(input: &[u8]) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        blake3::Hasher::new().update(input).finalize_xof().fill(&mut out);
+        out
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sha256Hasher;
+
+impl TreeHasher for Sha256Hasher {
+    const ID: u8 = 1;
+
+    fn hash(input: &[u8]) -> [u8; 32] {
+        Sha256::digest(input).into()
+    }
+}
+
+/// Poseidon sponge over the field `F`, for digests that are cheap to recompute inside SNARK
+/// circuits. Uses a width-3 permutation with alpha = 5, 8 full and 57 partial rounds.
+///
+/// Byte digests are the little-endian encoding of the squeezed element, so a tree over `F`
+/// recovers that element exactly. `F` defaults to the BLS12-381 scalar field used by
+/// [`KzgVc`](crate::KzgVc) and must fit in 256 bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoseidonHasher<F = ark_bls12_381::Fr>(PhantomData<F>);
+
+impl<F: PrimeField> PoseidonHasher<F> {
+    const RATE: usize = 2;
+    const FULL_ROUNDS: usize = 8;
+    const PARTIAL_ROUNDS: usize = 57;
+    const ALPHA: u64 = 5;
+
+    const FITS: () = assert!(F::MODULUS_BIT_SIZE <= 256, "field elements must fit in a 32-byte digest");
+
+    fn derive_config() -> PoseidonConfig<F> {
+        let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+            F::MODULUS_BIT_SIZE as u64,
+            Self::RATE,
+            Self::FULL_ROUNDS as u64,
+            Self::PARTIAL_ROUNDS as u64,
+            0,
+        );
+        PoseidonConfig::new(Self::FULL_ROUNDS, Self::PARTIAL_ROUNDS, Self::ALPHA, mds, ark, Self::RATE, 1)
+    }
+
+    // Round constants are derived from the Grain LFSR, which is too slow to redo for every hash.
+    #[cfg(feature = "std")]
+    fn config() -> PoseidonConfig<F> {
+        static CONFIGS: OnceLock<Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>> = OnceLock::new();
+        let mut configs = CONFIGS.get_or_init(Default::default).lock().expect("poseidon config cache");
+        configs
+            .entry(TypeId::of::<F>())
+            .or_insert_with(|| Box::new(Self::derive_config()))
+            .downcast_ref::<PoseidonConfig<F>>()
+            .expect("poseidon config type")
+            .clone()
+    }
+
+    // Without std there is nowhere to cache them, so verifiers derive them for every hash.
+    #[cfg(not(feature = "std"))]
+    fn config() -> PoseidonConfig<F> {
+        Self::derive_config()
+    }
+}
+
+impl<F: PrimeField> TreeHasher for PoseidonHasher<F> {
+    const ID: u8 = 2;
+
+    // hash_to_field keeps its default: reducing these bytes into F gives back the digest itself.
+    fn hash(input: &[u8]) -> [u8; 32] {
+        let () = Self::FITS;
+        let mut sponge = PoseidonSponge::<F>::new(&Self::config());
+        // Byte slices are absorbed with their length, so inputs of different lengths cannot collide
+        sponge.absorb(&input);
+        let digest: F = sponge.squeeze_native_field_elements(1)[0];
+        let mut out = [0u8; 32];
+        digest.serialize_compressed(&mut out[..]).expect("serialize digest");
+        out
+    }
+}
+
+/// Adapter that reduces a 64-byte digest of `H` into the field, so digests are uniform.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Wide<H>(PhantomData<H>);
+
+impl<H: TreeHasher> TreeHasher for Wide<H> {
+    const ID: u8 = H::ID | 0x80;
+    const SCHEME_VERSION: u8 = H::SCHEME_VERSION;
+
+    fn domain_prefix(domain: HashDomain) -> &'static [u8] {
+        H::domain_prefix(domain)
+    }
+
+    fn hash(input: &[u8]) -> [u8; 32] {
+        H::hash(input)
+    }
+
+    fn hash_wide(input: &[u8]) -> [u8; 64] {
+        H::hash_wide(input)
+    }
+
+    fn hash_to_field<F: PrimeField>(input: &[u8]) -> F {
+        F::from_le_bytes_mod_order(&H::hash_wide(input))
+    }
+}
+
+/// Commitment scheme version 1: every digest is prefixed with a tag naming its role, so no byte
+/// string can be read as both, say, a child commitment and a slot, and empty placeholders can
+/// never equal the digest of real data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DomainSeparated<H>(PhantomData<H>);
+
+impl<H: TreeHasher> TreeHasher for DomainSeparated<H> {
+    const ID: u8 = H::ID;
+    const SCHEME_VERSION: u8 = 1;
+
+    // All tags have the same length, so tagged inputs from different roles never coincide.
+    fn domain_prefix(domain: HashDomain) -> &'static [u8] {
+        match domain {
+            HashDomain::InternalChild => b"verkle-v1\x01",
+            HashDomain::ExtensionSlot => b"verkle-v1\x02",
+            HashDomain::EmptyChild => b"verkle-v1\x03",
+            HashDomain::EmptySlot => b"verkle-v1\x04",
+        }
+    }
+
+    fn hash(input: &[u8]) -> [u8; 32] {
+        H::hash(input)
+    }
+
+    fn hash_wide(input: &[u8]) -> [u8; 64] {
+        H::hash_wide(input)
+    }
+
+    fn hash_to_field<F: PrimeField>(input: &[u8]) -> F {
+        H::hash_to_field(input)
+    }
+}
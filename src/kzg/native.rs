@@ -0,0 +1,165 @@
+//! KZG implemented directly on `ark-ec`, for builds without `std`, where `ark-poly-commit`
+//! does not compile. Commitments, openings and setups are encoded exactly like the
+//! `ark-poly-commit` backend's, so either side can verify what the other produced.
+
+use alloc::{borrow::Cow, vec, vec::Vec};
+
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G1Projective, G2Affine};
+#[cfg(feature = "std")]
+use ark_bls12_381::G2Projective;
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{One, PrimeField, Zero};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as Domain};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "std")]
+use ark_std::UniformRand;
+
+use crate::{utils::evals_to_poly, vc::{VectorCommitment, ARITY}};
+
+/// KZG commitment to a vector: its interpolating polynomial evaluated at the secret point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Commitment(pub G1Affine);
+
+/// KZG opening at one index: the quotient `(p(X) - p(ω^i)) / (X - ω^i)` evaluated at the secret
+/// point. `random_v` is only set by hiding openings, which are rejected; it is kept so that
+/// openings decode like `ark-poly-commit`'s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Proof {
+    pub w: G1Affine,
+    pub random_v: Option<Fr>,
+}
+
+#[derive(Clone)]
+pub struct KzgVc<'a, const W: usize = ARITY> {
+    domain: Domain<Fr>,          // size W, fixed points {ω^i}
+    powers: Cow<'a, [G1Affine]>, // prover key [τ^i]G1 for i < W
+    h: G2Affine,                 // verifier key [1]G2 and [τ]G2
+    beta_h: G2Affine,
+}
+
+impl<'a, const W: usize> KzgVc<'a, W> {
+    #[cfg(feature = "std")]
+    pub fn setup(rng: &mut impl rand::RngCore) -> Result<Self, Box<dyn std::error::Error>> {
+        assert!(W.is_power_of_two(), "use a radix-2 domain for simplicity");
+        let domain = Domain::<Fr>::new(W).ok_or("no evaluation domain of this size")?;
+        // Toxic waste: τ only lives in this frame
+        let tau = Fr::rand(rng);
+        let g = G1Projective::rand(rng);
+        let h = G2Projective::rand(rng);
+
+        let mut powers = Vec::with_capacity(W);
+        let mut power = g;
+        for _ in 0..W {
+            powers.push(power);
+            power *= tau;
+        }
+
+        Ok(Self {
+            domain,
+            powers: Cow::Owned(G1Projective::normalize_batch(&powers)),
+            h: h.into_affine(),
+            beta_h: (h * tau).into_affine(),
+        })
+    }
+
+    /// Serializes the prover and verifier keys, so a setup can be shared and reloaded with
+    /// [`KzgVc::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.powers.serialize_compressed(&mut out).expect("serialize powers");
+        self.h.serialize_compressed(&mut out).expect("serialize verifier key");
+        self.beta_h.serialize_compressed(&mut out).expect("serialize verifier key");
+        out
+    }
+
+    /// Loads a setup written by [`KzgVc::to_bytes`]. Returns None unless `bytes` holds exactly
+    /// the keys for width `W`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        assert!(W.is_power_of_two(), "use a radix-2 domain for simplicity");
+        // The length prefix is checked before anything is allocated for it
+        let (len, mut bytes) = bytes.split_first_chunk::<8>()?;
+        if u64::from_le_bytes(*len) != W as u64 {
+            return None;
+        }
+        let powers = (0..W).map(|_| G1Affine::deserialize_compressed(&mut bytes).ok()).collect::<Option<Vec<_>>>()?;
+        let h = G2Affine::deserialize_compressed(&mut bytes).ok()?;
+        let beta_h = G2Affine::deserialize_compressed(&mut bytes).ok()?;
+        if !bytes.is_empty() {
+            return None;
+        }
+        Some(Self { domain: Domain::<Fr>::new(W)?, powers: Cow::Owned(powers), h, beta_h })
+    }
+
+    // Evaluates the polynomial with these coefficients at τ, in G1.
+    fn commit_coeffs(&self, coeffs: &[Fr]) -> G1Affine {
+        let scalars: Vec<_> = coeffs.iter().map(|c| c.into_bigint()).collect();
+        G1Projective::msm_bigint(&self.powers[..scalars.len()], &scalars).into_affine()
+    }
+
+    // Commitment to the i-th Lagrange basis polynomial, i.e. to the unit vector e_i.
+    fn lagrange_commitment(&self, index: usize) -> G1Affine {
+        let mut unit = [Fr::zero(); W];
+        unit[index] = Fr::one();
+        self.commit_from_children(&unit).0
+    }
+}
+
+// Quotient of p(X) - p(z) by X - z, by synthetic division from the leading coefficient.
+fn divide_by_linear(coeffs: &[Fr], z: Fr) -> Vec<Fr> {
+    let mut quotient = vec![Fr::zero(); coeffs.len().saturating_sub(1)];
+    let mut carry = Fr::zero();
+    for i in (1..coeffs.len()).rev() {
+        carry = carry * z + coeffs[i];
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
+
+impl<'a, const W: usize> VectorCommitment<W> for KzgVc<'a, W> {
+    type Fr = Fr;
+    type Commitment = Commitment;
+    type Proof = Proof;
+
+    fn commit_from_children(&self, children: &[Self::Fr; W]) -> Self::Commitment {
+        // Calculate coefficients
+        let poly = evals_to_poly(&self.domain, children);
+        Commitment(self.commit_coeffs(&poly.coeffs))
+    }
+
+    fn open_at(&self, evals: &[Self::Fr; W], index: usize) -> (Self::Fr, Self::Proof) {
+        let poly = evals_to_poly(&self.domain, evals);
+        let point = self.domain.element(index);
+        let witness = divide_by_linear(&poly.coeffs, point);
+        (evals[index], Proof { w: self.commit_coeffs(&witness), random_v: None })
+    }
+
+    fn verify_at(
+        &self,
+        comm: &Self::Commitment, index: usize, value: Self::Fr, proof: &Self::Proof,
+    ) -> bool {
+        if proof.random_v.is_some() {
+            return false;
+        }
+        // e(C - v·G, H) == e(W, τH - z·H), checked as a single product of pairings
+        let point = self.domain.element(index);
+        let lhs = comm.0.into_group() - self.powers[0] * value;
+        let rhs = self.beta_h.into_group() - self.h * point;
+        Bls12_381::multi_pairing([lhs, -proof.w.into_group()], [self.h.into_group(), rhs]).is_zero()
+    }
+
+    fn update_at(
+        &self,
+        comm: &Self::Commitment,
+        updates: &[(usize, Self::Fr, Self::Fr, &Self::Proof)],
+    ) -> Option<Self::Commitment> {
+        // Commitments are linear in the evaluations: C' = C + sum (new - old) * [L_i(tau)]
+        let mut acc = comm.0.into_group();
+        for &(index, old, new, proof) in updates {
+            if !self.verify_at(comm, index, old, proof) {
+                return None;
+            }
+            acc += self.lagrange_commitment(index) * (new - old);
+        }
+        Some(Commitment(acc.into_affine()))
+    }
+}
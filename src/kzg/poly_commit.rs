@@ -0,0 +1,228 @@
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{One, Zero};
+use ark_poly::{univariate::DensePolynomial, EvaluationDomain, Radix2EvaluationDomain as Domain, Polynomial};
+use ark_poly_commit::{kzg10::{Powers, UniversalParams, VerifierKey, KZG10}, PCCommitmentState};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::borrow::Cow;
+
+use crate::{utils::evals_to_poly, vc::{VectorCommitment, ARITY}};
+
+type Kzg = KZG10::<Bls12_381, DensePolynomial<Fr>>;
+
+/// KZG commitment to a vector: its interpolating polynomial evaluated at the secret point.
+pub type Commitment = ark_poly_commit::kzg10::Commitment<Bls12_381>;
+
+/// KZG opening at one index.
+pub type Proof = ark_poly_commit::kzg10::Proof<Bls12_381>;
+
+#[derive(Clone)]
+pub struct KzgVc<'a, const W: usize = ARITY> {
+    domain: Domain<Fr>,            // size W, fixed points {ω^i}
+    powers: Powers<'a, Bls12_381>, // trimmed prover key up to degree < W
+    vk: VerifierKey<Bls12_381>,
+}
+
+impl<'a, const W: usize> KzgVc<'a, W> {
+    pub fn setup(rng: &mut impl rand::RngCore) -> Result<Self, Box<dyn std::error::Error>> {
+        assert!(W.is_power_of_two(), "use a radix-2 domain for simplicity");
+        let domain = Domain::<Fr>::new(W).expect("domain");
+        // KZG universal setup for degree < k
+        let max_degree = W - 1;
+        let srs: UniversalParams<Bls12_381> =
+           Kzg::setup(max_degree, false, rng)?;
+
+        let powers_of_g = srs.powers_of_g[..W].to_vec();
+        let powers_of_gamma_g = (0..=W).map(|i| srs.powers_of_gamma_g[&i]).collect();
+
+        let powers = Powers {
+            powers_of_g: Cow::Owned(powers_of_g),
+            powers_of_gamma_g: Cow::Owned(powers_of_gamma_g),
+        };
+        let vk = VerifierKey {
+            g: srs.powers_of_g[0],
+            gamma_g: srs.powers_of_gamma_g[&0],
+            h: srs.h,
+            beta_h: srs.beta_h,
+            prepared_h: srs.prepared_h.clone(),
+            prepared_beta_h: srs.prepared_beta_h.clone(),
+        };
+
+        Ok(Self {
+            domain,
+            powers,
+            vk,
+        })
+    }
+
+    /// Serializes the prover and verifier keys, so a setup can be shared and reloaded with
+    /// [`KzgVc::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.powers.powers_of_g.serialize_compressed(&mut out).expect("serialize powers");
+        self.vk.h.serialize_compressed(&mut out).expect("serialize verifier key");
+        self.vk.beta_h.serialize_compressed(&mut out).expect("serialize verifier key");
+        out
+    }
+
+    /// Loads a setup written by [`KzgVc::to_bytes`]. Returns None unless `bytes` holds exactly
+    /// the keys for width `W`.
+    ///
+    /// Only the keys for non-hiding commitments are stored, so the loaded setup rejects hiding
+    /// openings, which this crate never produces.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        assert!(W.is_power_of_two(), "use a radix-2 domain for simplicity");
+        // The length prefix is checked before anything is allocated for it
+        let (len, mut bytes) = bytes.split_first_chunk::<8>()?;
+        if u64::from_le_bytes(*len) != W as u64 {
+            return None;
+        }
+        let powers_of_g = (0..W).map(|_| G1Affine::deserialize_compressed(&mut bytes).ok()).collect::<Option<Vec<_>>>()?;
+        let h = G2Affine::deserialize_compressed(&mut bytes).ok()?;
+        let beta_h = G2Affine::deserialize_compressed(&mut bytes).ok()?;
+        if !bytes.is_empty() {
+            return None;
+        }
+
+        let vk = VerifierKey {
+            g: powers_of_g[0],
+            gamma_g: G1Affine::zero(),
+            h,
+            beta_h,
+            prepared_h: h.into(),
+            prepared_beta_h: beta_h.into(),
+        };
+        let powers = Powers { powers_of_g: Cow::Owned(powers_of_g), powers_of_gamma_g: Cow::Owned(Vec::new()) };
+        Some(Self { domain: Domain::<Fr>::new(W)?, powers, vk })
+    }
+
+    // Commitment to the i-th Lagrange basis polynomial, i.e. to the unit vector e_i.
+    fn lagrange_commitment(&self, index: usize) -> G1Affine {
+        let mut unit = [Fr::zero(); W];
+        unit[index] = Fr::one();
+        self.commit_from_children(&unit).0
+    }
+}
+
+impl<'a, const W: usize> VectorCommitment<W> for KzgVc<'a, W> {
+    type Fr = Fr;
+    type Commitment = Commitment;
+    type Proof = Proof;
+
+    fn commit_from_children(&self, children: &[Self::Fr; W]) -> Self::Commitment {
+        // Calculate coefficients
+        let poly = evals_to_poly(&self.domain, children);
+
+        // Evaluate poly at the appropriate points
+        let (comm, _rand) = Kzg::commit(&self.powers, &poly, None, None)
+            .expect("commitment");
+
+        comm
+    }
+
+   fn open_at(&self, evals: &[Self::Fr; W], index: usize) -> (Self::Fr, Self::Proof) {
+       let poly = evals_to_poly(&self.domain, evals);
+       let point = self.domain.element(index);
+       let value = poly.evaluate(&point);
+
+       let rand = ark_poly_commit::kzg10::Randomness::empty();
+        let proof = Kzg::open(&self.powers, &poly, point, &rand)
+            .expect("open");
+        (value, proof)
+   }
+
+    fn verify_at(
+        &self,
+        comm: &Self::Commitment, index: usize, value: Self::Fr, proof: &Self::Proof,
+    ) -> bool {
+        // Openings are never hiding, see `from_bytes`
+        if proof.random_v.is_some() {
+            return false;
+        }
+        let point = self.domain.element(index);
+        Kzg::check(&self.vk, comm, point, value, proof).expect("verification")
+    }
+
+    fn update_at(
+        &self,
+        comm: &Self::Commitment,
+        updates: &[(usize, Self::Fr, Self::Fr, &Self::Proof)],
+    ) -> Option<Self::Commitment> {
+        // Commitments are linear in the evaluations: C' = C + sum (new - old) * [L_i(tau)]
+        let mut acc = comm.0.into_group();
+        for &(index, old, new, proof) in updates {
+            if !self.verify_at(comm, index, old, proof) {
+                return None;
+            }
+            acc += self.lagrange_commitment(index) * (new - old);
+        }
+        Some(ark_poly_commit::kzg10::Commitment(acc.into_affine()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ark_ff::UniformRand;
+
+    use super::*;
+
+    #[test]
+    fn test_kzg_vc() {
+        let mut rng = rand::thread_rng();
+        let kzg_vc = KzgVc::setup(&mut rng).expect("setup");
+
+        // Test commitment
+        let children = [Fr::rand(&mut rng); ARITY];
+        let comm = kzg_vc.commit_from_children(&children);
+
+        // Test opening
+        for (i, child) in children.iter().enumerate() {
+            let (value, proof) = kzg_vc.open_at(&children, i);
+            let is_valid = kzg_vc.verify_at(&comm, 0, value, &proof);
+            assert!(is_valid);
+            assert_eq!(&value, child);
+        }
+    }
+
+    #[test]
+    fn test_kzg_update_matches_recommit() {
+        let mut rng = rand::thread_rng();
+        let kzg_vc = KzgVc::setup(&mut rng).expect("setup");
+
+        let mut children: [Fr; ARITY] = std::array::from_fn(|_| Fr::rand(&mut rng));
+        let comm = kzg_vc.commit_from_children(&children);
+        let (old_a, proof_a) = kzg_vc.open_at(&children, 3);
+        let (old_b, proof_b) = kzg_vc.open_at(&children, 200);
+
+        let (new_a, new_b) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let updated = kzg_vc
+            .update_at(&comm, &[(3, old_a, new_a, &proof_a), (200, old_b, new_b, &proof_b)])
+            .expect("valid openings");
+
+        children[3] = new_a;
+        children[200] = new_b;
+        assert_eq!(updated, kzg_vc.commit_from_children(&children));
+
+        // An opening for the wrong old value is rejected
+        assert!(kzg_vc.update_at(&comm, &[(3, new_a, old_a, &proof_a)]).is_none());
+    }
+
+    #[test]
+    fn test_kzg_bytes_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let kzg_vc = KzgVc::setup(&mut rng).expect("setup");
+        let bytes = kzg_vc.to_bytes();
+        let loaded = KzgVc::<ARITY>::from_bytes(&bytes).expect("valid setup");
+
+        let children: [Fr; ARITY] = std::array::from_fn(|_| Fr::rand(&mut rng));
+        let comm = kzg_vc.commit_from_children(&children);
+        assert_eq!(loaded.commit_from_children(&children), comm);
+        let (value, proof) = kzg_vc.open_at(&children, 7);
+        assert!(loaded.verify_at(&comm, 7, value, &proof));
+        assert_eq!(loaded.open_at(&children, 7), (value, proof));
+
+        assert!(KzgVc::<ARITY>::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(KzgVc::<16>::from_bytes(&bytes).is_none());
+        assert!(KzgVc::<ARITY>::from_bytes(b"not a setup").is_none());
+    }
+}
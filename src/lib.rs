@@ -1,9 +1,57 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod compact;
+#[cfg(feature = "std")]
+pub mod concurrent;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod dot;
+#[cfg(feature = "std")]
+pub mod flat;
+pub mod hasher;
 pub mod kzg;
+pub mod merkle;
+#[cfg(feature = "std")]
+pub mod migrate;
+pub mod mock;
 pub mod node;
+#[cfg(feature = "std")]
+pub mod partial;
+#[cfg(feature = "std")]
+pub mod secure;
+#[cfg(feature = "std")]
+pub mod snap;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
 pub mod tree;
 pub mod vc;
+#[cfg(feature = "std")]
+pub mod version;
+#[cfg(feature = "std")]
+pub mod wal;
 mod utils;
 
+#[cfg(feature = "std")]
+pub use crate::concurrent::ConcurrentVerkleTree;
+#[cfg(feature = "std")]
+pub use crate::flat::FlatTree;
+pub use crate::hasher::TreeHasher;
 pub use crate::kzg::KzgVc;
+pub use crate::merkle::MerkleVc;
+pub use crate::mock::MockVc;
 pub use crate::node::Value;
+pub use crate::utils::{digest_commit, digest_slot};
+#[cfg(feature = "std")]
+pub use crate::partial::PartialTree;
+#[cfg(feature = "std")]
+pub use crate::secure::SecureVerkleTree;
+#[cfg(feature = "std")]
 pub use crate::tree::VerkleTree;
+#[cfg(feature = "std")]
+pub use crate::wal::WalTree;
@@ -0,0 +1,109 @@
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use ark_bls12_381::Fr;
+use ark_serialize::CanonicalSerialize;
+
+use crate::vc::{VectorCommitment, ARITY};
+
+const LEAF: u8 = 0;
+const INNER: u8 = 1;
+
+/// A vector commitment by a binary Merkle tree: the commitment is the Blake3 Merkle root over
+/// the W children, and an opening is the authentication path of log2(W) sibling hashes, leaf
+/// first, so 8 hashes for the default width.
+///
+/// It needs no trusted setup and relies only on the hash function, so it stays secure against
+/// quantum attacks, at the price of openings that grow with the width.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MerkleVc<const W: usize = ARITY>;
+
+impl<const W: usize> MerkleVc<W> {
+    const DEPTH: usize = {
+        assert!(W.is_power_of_two() && W >= 2, "width must be a power of two");
+        W.trailing_zeros() as usize
+    };
+
+    fn leaf(child: &Fr) -> [u8; 32] {
+        let mut bytes = Vec::with_capacity(1 + 32);
+        bytes.push(LEAF);
+        child.serialize_compressed(&mut bytes).expect("serialize field element");
+        *blake3::hash(&bytes).as_bytes()
+    }
+
+    fn inner(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&[INNER]);
+        hasher.update(left);
+        hasher.update(right);
+        *hasher.finalize().as_bytes()
+    }
+
+    // Every level of the tree, from the W leaves up to the root.
+    fn levels(children: &[Fr; W]) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![children.iter().map(Self::leaf).collect::<Vec<_>>()];
+        for level in 0..Self::DEPTH {
+            let parents = levels[level].chunks_exact(2).map(|pair| Self::inner(&pair[0], &pair[1])).collect();
+            levels.push(parents);
+        }
+        levels
+    }
+
+    // Root reached from `node` at `index` by climbing along `path`.
+    fn climb(mut node: [u8; 32], index: usize, path: &[[u8; 32]]) -> [u8; 32] {
+        for (level, sibling) in path.iter().enumerate() {
+            node = match (index >> level) & 1 {
+                0 => Self::inner(&node, sibling),
+                _ => Self::inner(sibling, &node),
+            };
+        }
+        node
+    }
+}
+
+impl<const W: usize> VectorCommitment<W> for MerkleVc<W> {
+    type Fr = Fr;
+    type Commitment = [u8; 32];
+    type Proof = Vec<[u8; 32]>; // sibling hashes, leaf level first
+
+    fn commit_from_children(&self, children: &[Self::Fr; W]) -> Self::Commitment {
+        Self::levels(children)[Self::DEPTH][0]
+    }
+
+    fn open_at(&self, children: &[Self::Fr; W], index: usize) -> (Self::Fr, Self::Proof) {
+        let levels = Self::levels(children);
+        let path = (0..Self::DEPTH).map(|level| levels[level][(index >> level) ^ 1]).collect();
+        (children[index], path)
+    }
+
+    fn verify_at(&self, commitment: &Self::Commitment, index: usize, value_digest: Self::Fr, proof: &Self::Proof) -> bool {
+        index < W && proof.len() == Self::DEPTH && Self::climb(Self::leaf(&value_digest), index, proof) == *commitment
+    }
+
+    fn update_at(&self, commitment: &Self::Commitment, updates: &[(usize, Self::Fr, Self::Fr, &Self::Proof)]) -> Option<Self::Commitment> {
+        // Nodes known from the openings, keyed by (level, position); paths may overlap
+        let mut nodes = BTreeMap::new();
+        for &(index, old, _, proof) in updates {
+            if !self.verify_at(commitment, index, old, proof) {
+                return None;
+            }
+            nodes.insert((0, index), Self::leaf(&old));
+            for (level, sibling) in proof.iter().enumerate() {
+                nodes.entry((level, (index >> level) ^ 1)).or_insert(*sibling);
+            }
+        }
+
+        // Each update rehashes its whole path, so ancestors shared with earlier updates are
+        // recomputed from their current children
+        let mut root = *commitment;
+        for &(index, _, new, _) in updates {
+            nodes.insert((0, index), Self::leaf(&new));
+            for level in 0..Self::DEPTH {
+                let left = (index >> level) & !1;
+                let parent = Self::inner(&nodes[&(level, left)], &nodes[&(level, left | 1)]);
+                nodes.insert((level + 1, index >> (level + 1)), parent);
+            }
+            root = nodes[&(Self::DEPTH, 0)];
+        }
+        Some(root)
+    }
+}
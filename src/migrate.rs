@@ -0,0 +1,378 @@
+//! Migration of an account/storage state dump from a hex-Patricia trie into a [`VerkleTree`].
+//!
+//! Keys follow the EIP-6800 layout: every account owns a header stem holding its version,
+//! balance, nonce, code hash and code size at suffixes 0..=4, the first 64 storage slots at
+//! 64..128 and the first 128 code chunks at 128..256. Other storage slots and code chunks live
+//! in further stems of the account. Stems are derived with the tree's hasher `H` in place of the
+//! Pedersen hash of the EIP. Values are 32 bytes, with integers in little-endian order.
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    tree::VerkleTree,
+    utils::decode_hex,
+    vc::VectorCommitment,
+    Value,
+};
+
+const VERSION_LEAF_KEY: u8 = 0;
+const BALANCE_LEAF_KEY: u8 = 1;
+const NONCE_LEAF_KEY: u8 = 2;
+const CODE_HASH_LEAF_KEY: u8 = 3;
+const CODE_SIZE_LEAF_KEY: u8 = 4;
+const HEADER_STORAGE_OFFSET: u64 = 64;
+const CODE_OFFSET: u64 = 128;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The dump is not valid JSON, or not an object with an `accounts` map.
+    Json(String),
+    /// A field of an account could not be parsed.
+    Field { account: String, field: &'static str },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Json(e) => write!(f, "invalid state dump: {e}"),
+            MigrationError::Field { account, field } => write!(f, "invalid {field} for account {account}"),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// One account of a state dump.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DumpAccount {
+    pub balance: [u8; 32], // little-endian
+    pub nonce: u64,
+    pub code_hash: [u8; 32],
+    pub code: Vec<u8>,
+    pub storage: BTreeMap<[u8; 32], [u8; 32]>, // slot -> value, both big-endian as in the dump
+}
+
+/// Accounts of a state dump, keyed by address.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateDump {
+    pub root: Option<[u8; 32]>, // root of the Patricia trie the dump was taken from
+    pub accounts: BTreeMap<[u8; 20], DumpAccount>,
+}
+
+/// An item of account state, addressed within its account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateItem {
+    Version,
+    Balance,
+    Nonce,
+    CodeHash,
+    CodeSize,
+    CodeChunk(u64),
+    Storage([u8; 32]),
+}
+
+impl StateDump {
+    /// Parses a dump in the format of geth's `dump` command:
+    /// `{"root": "0x..", "accounts": {"0x<address>": {"balance": "<decimal>", "nonce": <n>,
+    /// "codeHash": "0x..", "code": "0x..", "storage": {"0x<slot>": "0x<value>"}}}}`.
+    /// Storage must be keyed by slot rather than by hashed slot, i.e. dumped with preimages.
+    pub fn from_json(json: &str) -> Result<Self, MigrationError> {
+        let dump: serde_json::Value = serde_json::from_str(json).map_err(|e| MigrationError::Json(e.to_string()))?;
+        let accounts = dump.get("accounts").and_then(|a| a.as_object()).ok_or_else(|| MigrationError::Json("missing accounts".into()))?;
+        let root = match dump.get("root").and_then(|r| r.as_str()) {
+            Some(r) => Some(parse_word(r).ok_or_else(|| MigrationError::Json("invalid root".into()))?),
+            None => None,
+        };
+
+        let mut out = StateDump { root, accounts: BTreeMap::new() };
+        for (address, account) in accounts {
+            let field = |field| MigrationError::Field { account: address.clone(), field };
+            let addr: [u8; 20] = parse_hex(address).and_then(|a| a.try_into().ok()).ok_or_else(|| field("address"))?;
+
+            let mut parsed = DumpAccount::default();
+            if let Some(balance) = account.get("balance") {
+                parsed.balance = parse_uint(balance).ok_or_else(|| field("balance"))?;
+            }
+            if let Some(nonce) = account.get("nonce") {
+                let nonce = parse_uint(nonce).ok_or_else(|| field("nonce"))?;
+                if nonce[8..].iter().any(|&b| b != 0) {
+                    return Err(field("nonce"));
+                }
+                parsed.nonce = u64::from_le_bytes(nonce[..8].try_into().expect("8 bytes"));
+            }
+            if let Some(hash) = account.get("codeHash") {
+                parsed.code_hash = hash.as_str().and_then(parse_word).ok_or_else(|| field("codeHash"))?;
+            }
+            if let Some(code) = account.get("code") {
+                parsed.code = code.as_str().and_then(parse_hex).ok_or_else(|| field("code"))?;
+            }
+            if let Some(storage) = account.get("storage") {
+                for (slot, value) in storage.as_object().ok_or_else(|| field("storage"))? {
+                    let slot = parse_word(slot).ok_or_else(|| field("storage"))?;
+                    let value = value.as_str().and_then(parse_word).ok_or_else(|| field("storage"))?;
+                    parsed.storage.insert(slot, value);
+                }
+            }
+            out.accounts.insert(addr, parsed);
+        }
+        Ok(out)
+    }
+}
+
+impl DumpAccount {
+    /// Number of 31-byte code chunks.
+    pub fn code_chunks(&self) -> u64 {
+        self.code.len().div_ceil(31) as u64
+    }
+
+    /// The value this account stores at `item`, encoded as in the tree.
+    pub fn value(&self, item: StateItem) -> Option<Value> {
+        let word = match item {
+            StateItem::Version => [0u8; 32],
+            StateItem::Balance => self.balance,
+            StateItem::Nonce => le_word(self.nonce),
+            StateItem::CodeHash => self.code_hash,
+            StateItem::CodeSize => le_word(self.code.len() as u64),
+            StateItem::CodeChunk(i) if i < self.code_chunks() => chunkify_code(&self.code)[i as usize],
+            StateItem::CodeChunk(_) => return None,
+            StateItem::Storage(slot) => *self.storage.get(&slot)?,
+        };
+        Some(Value::from(word.to_vec()))
+    }
+
+    /// Every tree entry of the account at `address`.
+    pub fn entries<H: TreeHasher>(&self, address: &[u8; 20]) -> Vec<([u8; 32], Value)> {
+        let mut items = vec![StateItem::Version, StateItem::Balance, StateItem::Nonce, StateItem::CodeHash, StateItem::CodeSize];
+        items.extend(self.storage.keys().map(|slot| StateItem::Storage(*slot)));
+        items.extend((0..self.code_chunks()).map(StateItem::CodeChunk));
+
+        let chunks = chunkify_code(&self.code);
+        items
+            .into_iter()
+            .map(|item| {
+                // Chunk the code once rather than once per chunk
+                let value = match item {
+                    StateItem::CodeChunk(i) => Value::from(chunks[i as usize].to_vec()),
+                    _ => self.value(item).expect("listed item is present"),
+                };
+                (tree_key::<H>(address, item), value)
+            })
+            .collect()
+    }
+}
+
+/// Tree key of `item` of the account at `address`.
+pub fn tree_key<H: TreeHasher>(address: &[u8; 20], item: StateItem) -> [u8; 32] {
+    // Position in the account's key space, as (tree index, suffix) with a big-endian tree index
+    let (tree_index, suffix) = match item {
+        StateItem::Version => ([0u8; 32], VERSION_LEAF_KEY),
+        StateItem::Balance => ([0u8; 32], BALANCE_LEAF_KEY),
+        StateItem::Nonce => ([0u8; 32], NONCE_LEAF_KEY),
+        StateItem::CodeHash => ([0u8; 32], CODE_HASH_LEAF_KEY),
+        StateItem::CodeSize => ([0u8; 32], CODE_SIZE_LEAF_KEY),
+        StateItem::CodeChunk(i) => position(CODE_OFFSET as u128 + i as u128),
+        StateItem::Storage(slot) if slot[..31].iter().all(|&b| b == 0) && (slot[31] as u64) < CODE_OFFSET - HEADER_STORAGE_OFFSET => {
+            ([0u8; 32], (HEADER_STORAGE_OFFSET + slot[31] as u64) as u8)
+        }
+        StateItem::Storage(slot) => {
+            // MAIN_STORAGE_OFFSET + slot, where MAIN_STORAGE_OFFSET = 256^31: the tree index is
+            // slot / 256 + 256^30 and the suffix is slot % 256
+            let mut index = [0u8; 32];
+            index[1..].copy_from_slice(&slot[..31]);
+            let (sum, carry) = index[1].overflowing_add(1);
+            index[1] = sum;
+            index[0] += carry as u8;
+            (index, slot[31])
+        }
+    };
+
+    let mut input = [0u8; 64];
+    input[12..32].copy_from_slice(address);
+    input[32..].copy_from_slice(&tree_index);
+    input[32..].reverse(); // the tree index is hashed little-endian
+    let mut key = H::hash(&input);
+    key[31] = suffix;
+    key
+}
+
+// (tree index, suffix) of a position below 2^128 in an account's key space.
+fn position(pos: u128) -> ([u8; 32], u8) {
+    let mut index = [0u8; 32];
+    index[16..].copy_from_slice(&(pos >> 8).to_be_bytes());
+    (index, pos as u8)
+}
+
+fn le_word(n: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[..8].copy_from_slice(&n.to_le_bytes());
+    word
+}
+
+// Splits code into 31-byte chunks, each prefixed with how many of its leading bytes are PUSH
+// data carried over from the previous chunk.
+fn chunkify_code(code: &[u8]) -> Vec<[u8; 32]> {
+    const PUSH1: u8 = 0x60;
+    const PUSH32: u8 = 0x7f;
+
+    let mut chunks = Vec::with_capacity(code.len().div_ceil(31));
+    let mut pushdata_until: usize = 0; // first offset after the current PUSH's data
+    for (i, bytes) in code.chunks(31).enumerate() {
+        let start = i * 31;
+        let mut chunk = [0u8; 32];
+        chunk[0] = pushdata_until.saturating_sub(start).min(31) as u8;
+        chunk[1..1 + bytes.len()].copy_from_slice(bytes);
+
+        for (offset, &op) in bytes.iter().enumerate() {
+            let pc = start + offset;
+            if pc >= pushdata_until && (PUSH1..=PUSH32).contains(&op) {
+                pushdata_until = pc + 1 + (op - PUSH1 + 1) as usize;
+            }
+        }
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+// Hex that may have an odd number of digits, as quantities in dumps often do.
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if s.len() % 2 == 1 { decode_hex(&format!("0{s}")) } else { decode_hex(s) }
+}
+
+// A big-endian hex word of at most 32 bytes, left-padded.
+fn parse_word(s: &str) -> Option<[u8; 32]> {
+    let bytes = parse_hex(s)?;
+    if bytes.len() > 32 {
+        return None;
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    Some(word)
+}
+
+// An unsigned integer given as a JSON number, a decimal string or a 0x-prefixed hex string,
+// as a little-endian word.
+fn parse_uint(value: &serde_json::Value) -> Option<[u8; 32]> {
+    if let Some(n) = value.as_u64() {
+        return Some(le_word(n));
+    }
+    let s = value.as_str()?;
+    if s.starts_with("0x") {
+        let mut word = parse_word(s)?;
+        word.reverse();
+        return Some(word);
+    }
+
+    let mut word = [0u8; 32];
+    for digit in s.chars() {
+        let mut carry = digit.to_digit(10)?;
+        for byte in word.iter_mut() {
+            let v = *byte as u32 * 10 + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            return None; // overflows 256 bits
+        }
+    }
+    (!s.is_empty()).then_some(word)
+}
+
+/// Outcome of [`migrate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MigrationReport<C> {
+    pub mpt_root: Option<[u8; 32]>,
+    pub verkle_root: C,
+    pub accounts: usize,
+    pub storage_slots: usize,
+    pub code_chunks: usize,
+    pub keys: usize, // tree entries written
+}
+
+/// Converts `dump` into a new tree and reports what was migrated.
+pub fn migrate<V: VectorCommitment, H: TreeHasher>(vc: V, dump: &StateDump) -> (VerkleTree<V, H>, MigrationReport<V::Commitment>) {
+    let mut tree = VerkleTree::<V, H>::new(vc);
+    let mut report = MigrationReport {
+        mpt_root: dump.root,
+        verkle_root: V::Commitment::default(),
+        accounts: 0,
+        storage_slots: 0,
+        code_chunks: 0,
+        keys: 0,
+    };
+
+    for (address, account) in &dump.accounts {
+        let entries = account.entries::<H>(address);
+        report.accounts += 1;
+        report.storage_slots += account.storage.len();
+        report.code_chunks += account.code_chunks() as usize;
+        report.keys += entries.len();
+        tree.insert_batch(entries);
+    }
+    report.verkle_root = tree.commit();
+    (tree, report)
+}
+
+/// Overlay transition: the new tree takes all writes, while reads fall back to the old state
+/// for accounts that have not been converted yet. [`Overlay::convert`] moves accounts across a
+/// few at a time, in address order, without overwriting anything written since.
+pub struct Overlay<V: VectorCommitment, H: TreeHasher = Blake3Hasher> {
+    old: StateDump,
+    tree: VerkleTree<V, H>,
+    next: Option<[u8; 20]>, // next account to convert, None once all are
+}
+
+impl<V: VectorCommitment, H: TreeHasher> Overlay<V, H> {
+    pub fn new(vc: V, old: StateDump) -> Self {
+        let next = old.accounts.keys().next().copied();
+        Overlay { old, tree: VerkleTree::new(vc), next }
+    }
+
+    pub fn is_converted(&self, address: &[u8; 20]) -> bool {
+        self.next.is_none_or(|next| *address < next)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next.is_none()
+    }
+
+    pub fn get(&self, address: &[u8; 20], item: StateItem) -> Option<Value> {
+        if let Some(value) = self.tree.get(tree_key::<H>(address, item)) {
+            return Some(value);
+        }
+        if self.is_converted(address) {
+            return None;
+        }
+        self.old.accounts.get(address)?.value(item)
+    }
+
+    pub fn insert(&mut self, address: &[u8; 20], item: StateItem, value: Value) {
+        self.tree.insert(tree_key::<H>(address, item), value);
+    }
+
+    /// Converts up to `max_accounts` more accounts and returns how many were converted.
+    pub fn convert(&mut self, max_accounts: usize) -> usize {
+        let Some(next) = self.next else { return 0 };
+        let mut batch = Vec::new();
+        let mut pending = self.old.accounts.range(next..);
+        let mut converted = 0;
+
+        for (address, account) in pending.by_ref().take(max_accounts) {
+            // Keys written during the transition are newer than the old state
+            batch.extend(account.entries::<H>(address).into_iter().filter(|(key, _)| self.tree.get(*key).is_none()));
+            converted += 1;
+        }
+        self.next = pending.next().map(|(address, _)| *address);
+        self.tree.insert_batch(batch);
+        converted
+    }
+
+    pub fn tree(&self) -> &VerkleTree<V, H> {
+        &self.tree
+    }
+
+    pub fn tree_mut(&mut self) -> &mut VerkleTree<V, H> {
+        &mut self.tree
+    }
+}
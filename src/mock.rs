@@ -0,0 +1,65 @@
+use alloc::vec::Vec;
+
+use ark_bls12_381::Fr;
+use ark_serialize::CanonicalSerialize;
+
+use crate::vc::{VectorCommitment, ARITY};
+
+const DOMAIN: &[u8] = b"verkle-mock-vc";
+
+/// A vector commitment for tests: the commitment is a Blake3 hash of the vector, and an opening
+/// reveals the whole vector.
+///
+/// It is binding, so a tree or proof that links the wrong digests still fails to verify, but
+/// openings are as large as the vector and hide nothing. It needs no setup and commits in
+/// microseconds, which makes it suitable for exercising tree logic at scale, never for
+/// production proofs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MockVc<const W: usize = ARITY>;
+
+impl<const W: usize> MockVc<W> {
+    fn hash(children: &[Fr]) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(DOMAIN);
+        let mut bytes = Vec::new();
+        for child in children {
+            bytes.clear();
+            child.serialize_compressed(&mut bytes).expect("serialize field element");
+            hasher.update(&bytes);
+        }
+        *hasher.finalize().as_bytes()
+    }
+}
+
+impl<const W: usize> VectorCommitment<W> for MockVc<W> {
+    type Fr = Fr;
+    type Commitment = [u8; 32];
+    type Proof = Vec<Fr>; // the whole vector
+
+    fn commit_from_children(&self, children: &[Self::Fr; W]) -> Self::Commitment {
+        Self::hash(children)
+    }
+
+    fn open_at(&self, children: &[Self::Fr; W], index: usize) -> (Self::Fr, Self::Proof) {
+        (children[index], children.to_vec())
+    }
+
+    fn verify_at(&self, commitment: &Self::Commitment, index: usize, value_digest: Self::Fr, proof: &Self::Proof) -> bool {
+        proof.len() == W && proof.get(index) == Some(&value_digest) && Self::hash(proof) == *commitment
+    }
+
+    fn update_at(&self, commitment: &Self::Commitment, updates: &[(usize, Self::Fr, Self::Fr, &Self::Proof)]) -> Option<Self::Commitment> {
+        // Every opening reveals the same vector, so any of them can be patched and rehashed
+        let Some((_, _, _, proof)) = updates.first() else {
+            return Some(*commitment);
+        };
+        let mut children = proof.to_vec();
+        for &(index, old, new, proof) in updates {
+            if !self.verify_at(commitment, index, old, proof) {
+                return None;
+            }
+            children[index] = new;
+        }
+        Some(Self::hash(&children))
+    }
+}
@@ -1,52 +1,148 @@
-use crate::{utils::{ZERO_CHILD, ZERO_VALUE}, vc::VectorCommitment};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
 
-pub(crate) type Stem = [u8; 31];
+use bytes::Bytes;
+
+#[cfg(feature = "std")]
+use crate::vc::{VectorCommitment, ARITY};
+
+/// A key with its suffix bits cleared, see [`split_key`].
+pub(crate) type Stem = [u8; 32];
 pub(crate) type Suffix = u8;
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct Value(pub Vec<u8>);
+/// A stored value. Backed by [`Bytes`], so clones share the buffer and handing a value to a
+/// proof, a snapshot or the network does not copy it.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Value(pub Bytes);
+
+impl From<Bytes> for Value {
+    fn from(bytes: Bytes) -> Self {
+        Value(bytes)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value(Bytes::from(bytes))
+    }
+}
+
+impl From<&'static [u8]> for Value {
+    fn from(bytes: &'static [u8]) -> Self {
+        Value(Bytes::from_static(bytes))
+    }
+}
+
+impl AsRef<[u8]> for Value {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
 
-pub(crate) enum Node<V: VectorCommitment> {
+// Children are shared between the tree and the versions recorded by `commit_version`, and are
+// copied on write; see `crate::version`.
+#[cfg(feature = "std")]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Node<V: VectorCommitment<W>, const W: usize = ARITY> {
     Internal {
-        children: [Option<Box<Node<V>>>; 256],
-        commitments: [V::Fr; 256]
+        children: [Option<Arc<Node<V, W>>>; W],
+        commitments: [V::Fr; W]
     },
     Extension {
         stem: Stem,
-        slots: [Option<Value>; 256],
-        slot_commitment: [V::Fr; 256]
+        slots: [Option<Value>; W],
+        slot_commitment: [V::Fr; W]
     },
 }
 
-pub(crate) fn split_key(key: [u8; 32]) -> (Stem, Suffix) {
-    // Take last element
-    let suf = key[31];
+#[cfg(feature = "std")]
+impl<V: VectorCommitment<W>, const W: usize> Clone for Node<V, W> {
+    fn clone(&self) -> Self {
+        match self {
+            Node::Internal { children, commitments } => Node::Internal { children: children.clone(), commitments: *commitments },
+            Node::Extension { stem, slots, slot_commitment } => Node::Extension { stem: *stem, slots: slots.clone(), slot_commitment: *slot_commitment },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// Placeholder digests for a node that has not been committed yet; `commit` overwrites them.
+pub(crate) fn empty_digests<V: VectorCommitment<W>, const W: usize>() -> [V::Fr; W] {
+    core::array::from_fn(|_| V::Fr::default())
+}
 
-    let stem = key[..31].try_into().unwrap();
-    (stem, suf)
+/// Splits `key` for a width-W tree: the suffix is its low log2(W) bits and the stem is the key
+/// with those bits cleared, so every key has a place in the tree.
+pub(crate) fn split_key<const W: usize>(key: [u8; 32]) -> (Stem, Suffix) {
+    let mask = (W - 1) as u8;
+    let mut stem = key;
+    stem[31] &= !mask;
+    (stem, key[31] & mask)
 }
 
-pub struct ExtensionNode {
+/// The key stored at `suf` under `stem`; the inverse of [`split_key`].
+pub(crate) fn join_key(stem: &Stem, suf: Suffix) -> [u8; 32] {
+    let mut key = *stem;
+    key[31] |= suf;
+    key
+}
+
+/// Number of stem bits consumed per level in a width-W tree.
+pub(crate) const fn digit_bits<const W: usize>() -> usize {
+    W.trailing_zeros() as usize
+}
+
+/// Number of digits in a stem, i.e. the depth at which every stem has been fully consumed. For
+/// widths whose digits do not divide the stem bits, the last digit is padded with the cleared
+/// suffix bits.
+pub(crate) const fn stem_digits<const W: usize>() -> usize {
+    (256 - digit_bits::<W>()).div_ceil(digit_bits::<W>())
+}
+
+/// Number of leading bytes of a stem that can be non-zero, which is what formats store.
+#[cfg(feature = "std")]
+pub(crate) const fn stem_len<const W: usize>() -> usize {
+    (256 - digit_bits::<W>()).div_ceil(8)
+}
+
+/// The `level`-th log2(W)-bit digit of `stem`, most significant bits first.
+pub(crate) fn stem_digit<const W: usize>(stem: &Stem, level: usize) -> usize {
+    let bits = digit_bits::<W>();
+    if bits == 8 {
+        return stem[level] as usize;
+    }
+    let mut digit = 0;
+    for bit in level * bits..(level + 1) * bits {
+        let b = (stem[bit / 8] >> (7 - bit % 8)) & 1;
+        digit = (digit << 1) | b as usize;
+    }
+    digit
+}
+
+#[cfg(feature = "std")]
+pub struct ExtensionNode<const W: usize = ARITY> {
     pub stem: Stem,
-    pub slots: [Option<Value>; 256],
+    pub slots: [Option<Value>; W],
 }
 
-/// Replaces an encountered Extension(old_ext) with an Internal subtree that forks at the first differing byte vs new_stem.
-/// Caller must pass the start_depth = number of stem bytes already consumed on the path to old_ext.
-pub(crate) fn split_extension<V: VectorCommitment>(start_depth: usize, old_ext: ExtensionNode, new_stem: Stem, suf: Suffix, value: Value) -> Node<V> {
+#[cfg(feature = "std")]
+/// Replaces an encountered Extension(old_ext) with an Internal subtree that forks at the first differing digit vs new_stem.
+/// Caller must pass the start_depth = number of stem digits already consumed on the path to old_ext.
+pub(crate) fn split_extension<V: VectorCommitment<W>, const W: usize>(start_depth: usize, old_ext: ExtensionNode<W>, new_stem: Stem, suf: Suffix, value: Value) -> Node<V, W> {
     let old_stem = old_ext.stem;
-    // Get first index where the stems differ
-    let d = first_diff_index(old_stem, new_stem);
+    // Get first digit where the stems differ
+    let d = first_diff_digit::<W>(&old_stem, &new_stem);
 
-    debug_assert!(d < 31, "split_extension called with identical stems");
+    debug_assert!(d < stem_digits::<W>(), "split_extension called with identical stems");
     debug_assert!(
         old_ext.stem != new_stem,
         "split_extension called with identical stems"
     );
 
     let mut node = Node::Internal {
-        children: std::array::from_fn(|_| None),
-        commitments: std::array::from_fn(|_| ZERO_CHILD::<V>()),
+        children: core::array::from_fn(|_| None),
+        commitments: empty_digests::<V, W>(),
     };
     let mut cur = &mut node;
 
@@ -56,15 +152,15 @@ pub(crate) fn split_extension<V: VectorCommitment>(start_depth: usize, old_ext:
     );
 
     // Create a node with internals till stems differ
-    for i in start_depth..d {
+    for level in start_depth..d {
         match cur {
             Node::Internal { children, ..} => {
-                let idx = old_stem[i] as usize;
-                children[idx] = Some(Box::new(Node::Internal {
-                    children: std::array::from_fn(|_| None),
-                    commitments: std::array::from_fn(|_| ZERO_CHILD::<V>()),
+                let idx = stem_digit::<W>(&old_stem, level);
+                children[idx] = Some(Arc::new(Node::Internal {
+                    children: core::array::from_fn(|_| None),
+                    commitments: empty_digests::<V, W>(),
                 }));
-                cur = children[idx].as_deref_mut().unwrap();
+                cur = Arc::get_mut(children[idx].as_mut().unwrap()).expect("fresh node is unshared");
             }
             Node::Extension { .. } => unreachable!("Unexpected Extension node while splitting"),
         }
@@ -73,21 +169,21 @@ pub(crate) fn split_extension<V: VectorCommitment>(start_depth: usize, old_ext:
     // Once we have reached the first difference, we can now create two extension nodes
     match cur {
         Node::Internal { children, .. } => {
-            let old_idx = old_stem[d] as usize;
-            let new_idx = new_stem[d] as usize;
+            let old_idx = stem_digit::<W>(&old_stem, d);
+            let new_idx = stem_digit::<W>(&new_stem, d);
 
-            children[old_idx] = Some(Box::new(Node::Extension {
+            children[old_idx] = Some(Arc::new(Node::Extension {
                 stem: old_stem,
                 slots: old_ext.slots,
-                slot_commitment: std::array::from_fn(|_| ZERO_VALUE::<V>()),
+                slot_commitment: empty_digests::<V, W>(),
             }));
 
-            let mut new_slots: [Option<Value>; 256] = std::array::from_fn(|_| None);
+            let mut new_slots: [Option<Value>; W] = core::array::from_fn(|_| None);
             new_slots[suf as usize] = Some(value);
-            children[new_idx] = Some(Box::new(Node::Extension {
+            children[new_idx] = Some(Arc::new(Node::Extension {
                 stem: new_stem,
                 slots: new_slots,
-                slot_commitment: std::array::from_fn(|_| ZERO_VALUE::<V>()),
+                slot_commitment: empty_digests::<V, W>(),
             }));
 
         }
@@ -97,11 +193,41 @@ pub(crate) fn split_extension<V: VectorCommitment>(start_depth: usize, old_ext:
     node
 }
 
-fn first_diff_index(old_stem: Stem, new_stem: Stem) -> usize {
-    for i in 0..31 {
-        if old_stem[i] != new_stem[i] {
-            return i;
+#[cfg(feature = "std")]
+/// Checks that the subtree at `node`, reached by consuming the digits in `path`, has canonical shape.
+/// Returns the number of stems stored below it, or None if the shape depends on insertion history.
+pub(crate) fn canonical_stems<V: VectorCommitment<W>, const W: usize>(node: &Node<V, W>, path: &mut Vec<u8>) -> Option<usize> {
+    match node {
+        Node::Internal { children, .. } => {
+            if path.len() >= stem_digits::<W>() {
+                return None;
+            }
+            let mut stems = 0;
+            for (i, child) in children.iter().enumerate() {
+                if let Some(child) = child.as_deref() {
+                    path.push(i as u8);
+                    let below = canonical_stems(child, path);
+                    path.pop();
+                    stems += below?;
+                }
+            }
+            // A single stem must be stored as an Extension, never behind a chain of Internals
+            (stems >= 2).then_some(stems)
+        }
+        Node::Extension { stem, slots, .. } => {
+            let placed = path.iter().enumerate().all(|(level, &digit)| stem_digit::<W>(stem, level) == digit as usize);
+            let occupied = slots.iter().any(Option::is_some);
+            (placed && occupied).then_some(1)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn first_diff_digit<const W: usize>(old_stem: &Stem, new_stem: &Stem) -> usize {
+    for level in 0..stem_digits::<W>() {
+        if stem_digit::<W>(old_stem, level) != stem_digit::<W>(new_stem, level) {
+            return level;
         }
     }
-    31
+    stem_digits::<W>()
 }
@@ -0,0 +1,204 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt, marker::PhantomData};
+
+use bytes::Bytes;
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    node::{join_key, split_key, stem_digit, Stem, Value},
+    utils::{digest_commit, digest_slot},
+    vc::{verify_proof, Step, VectorCommitment, VerkleProof, ARITY},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartialTreeError {
+    /// The proof for this key does not verify against the pre-state root.
+    InvalidProof([u8; 32]),
+    /// The proof for this key disagrees with another proof about a shared node.
+    InconsistentWitness([u8; 32]),
+    /// The key was not covered by the witness, so its path commitments are unknown.
+    NotWitnessed([u8; 32]),
+    /// The vector commitment rejected an opening while recomputing a commitment, or cannot
+    /// update a commitment from openings at all.
+    UpdateRejected,
+}
+
+impl fmt::Display for PartialTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartialTreeError::InvalidProof(k) => write!(f, "invalid proof for key {k:02x?}"),
+            PartialTreeError::InconsistentWitness(k) => write!(f, "inconsistent witness for key {k:02x?}"),
+            PartialTreeError::NotWitnessed(k) => write!(f, "key {k:02x?} is outside the witness"),
+            PartialTreeError::UpdateRejected => write!(f, "vector commitment rejected an update"),
+        }
+    }
+}
+
+impl std::error::Error for PartialTreeError {}
+
+// An opened slot: its value in the pre-state, the opening of that value, and any pending write.
+struct Slot<V: VectorCommitment<W>, const W: usize> {
+    value: Bytes,
+    proof: V::Proof,
+    write: Option<Value>,
+}
+
+enum PartialNode<V: VectorCommitment<W>, const W: usize> {
+    Internal {
+        commit: V::Commitment,
+        children: BTreeMap<usize, (V::Fr, V::Proof)>, // opened child digests
+    },
+    Extension {
+        commit: V::Commitment,
+        stem: Stem,
+        slots: BTreeMap<usize, Slot<V, W>>, // opened slots
+    },
+}
+
+/// The part of a tree that is opened by a set of proofs, for stateless clients.
+///
+/// Built from a pre-state root and a witness of `(key, proof)` pairs. It accepts writes to the
+/// witnessed keys and recomputes the post-state root from the opened commitments alone.
+pub struct PartialTree<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    vc: V,
+    root: V::Commitment,
+    nodes: BTreeMap<Vec<u8>, PartialNode<V, W>>, // keyed by the stem digits consumed to reach the node
+    extensions: BTreeMap<Stem, Vec<u8>>,         // path of the Extension node of each stem
+    hasher: PhantomData<H>,
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> PartialTree<V, H, W> {
+    /// Verifies every proof in `witness` against `root` and keeps the nodes they open.
+    pub fn from_witness(vc: V, root: V::Commitment, witness: &[([u8; 32], VerkleProof<V, H, W>)]) -> Result<Self, PartialTreeError> {
+        let mut tree = PartialTree { vc, root, nodes: BTreeMap::new(), extensions: BTreeMap::new(), hasher: PhantomData };
+        for (key, proof) in witness {
+            if !verify_proof(&tree.vc, &tree.root, proof, *key) {
+                return Err(PartialTreeError::InvalidProof(*key));
+            }
+            tree.add_proof(*key, proof)?;
+        }
+        Ok(tree)
+    }
+
+    fn add_proof(&mut self, key: [u8; 32], proof: &VerkleProof<V, H, W>) -> Result<(), PartialTreeError> {
+        let (stem, suf) = split_key::<W>(key);
+        let inconsistent = PartialTreeError::InconsistentWitness(key);
+
+        for (depth, step) in proof.steps.iter().enumerate() {
+            let path: Vec<u8> = (0..depth).map(|level| stem_digit::<W>(&stem, level) as u8).collect();
+            match step {
+                Step::Internal { parent_commit, index, child_digest, proof } => {
+                    let node = self.nodes.entry(path).or_insert_with(|| PartialNode::Internal {
+                        commit: parent_commit.clone(),
+                        children: BTreeMap::new(),
+                    });
+                    match node {
+                        PartialNode::Internal { commit, children } if commit == parent_commit => {
+                            let (digest, _) = children.entry(*index).or_insert_with(|| (*child_digest, proof.clone()));
+                            if digest != child_digest {
+                                return Err(inconsistent);
+                            }
+                        }
+                        _ => return Err(inconsistent),
+                    }
+                }
+                Step::Extension { ext_commit, index, proof: opening } => {
+                    self.extensions.entry(stem).or_insert_with(|| path.clone());
+                    let node = self.nodes.entry(path).or_insert_with(|| PartialNode::Extension {
+                        commit: ext_commit.clone(),
+                        stem,
+                        slots: BTreeMap::new(),
+                    });
+                    match node {
+                        PartialNode::Extension { commit, stem: node_stem, slots } if commit == ext_commit && *node_stem == stem => {
+                            let slot = slots.entry(*index).or_insert_with(|| Slot {
+                                value: proof.value.clone(),
+                                proof: opening.clone(),
+                                write: None,
+                            });
+                            if slot.value != proof.value {
+                                return Err(inconsistent);
+                            }
+                        }
+                        _ => return Err(inconsistent),
+                    }
+                    debug_assert_eq!(*index, suf as usize);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn slot(&self, key: [u8; 32]) -> Option<&Slot<V, W>> {
+        let (stem, suf) = split_key::<W>(key);
+        match self.nodes.get(self.extensions.get(&stem)?)? {
+            PartialNode::Extension { slots, .. } => slots.get(&(suf as usize)),
+            PartialNode::Internal { .. } => None,
+        }
+    }
+
+    /// Current value of a witnessed key, including pending writes.
+    pub fn get(&self, key: [u8; 32]) -> Option<Value> {
+        self.slot(key).map(|slot| match &slot.write {
+            Some(v) => v.clone(),
+            None => Value(slot.value.clone()),
+        })
+    }
+
+    /// Writes `value` at `key`, which must have been proven by the witness.
+    pub fn insert(&mut self, key: [u8; 32], value: Value) -> Result<(), PartialTreeError> {
+        let (stem, suf) = split_key::<W>(key);
+        let node = self.extensions.get(&stem).and_then(|path| self.nodes.get_mut(path));
+        let slot = match node {
+            Some(PartialNode::Extension { slots, .. }) => slots.get_mut(&(suf as usize)),
+            _ => None,
+        };
+        match slot {
+            Some(slot) => {
+                slot.write = Some(value);
+                Ok(())
+            }
+            None => Err(PartialTreeError::NotWitnessed(key)),
+        }
+    }
+
+    /// Computes the post-state root after all writes, starting from the pre-state openings.
+    pub fn root(&self) -> Result<V::Commitment, PartialTreeError> {
+        let mut updated: BTreeMap<Vec<u8>, V::Commitment> = BTreeMap::new();
+
+        // A child's path extends its parent's, so deeper nodes are finished before their parents
+        let mut paths: Vec<&Vec<u8>> = self.nodes.keys().collect();
+        paths.sort_by_key(|p| Reverse(p.len()));
+
+        for path in paths {
+            let mut changes = Vec::new();
+            let commit = match &self.nodes[path] {
+                PartialNode::Internal { commit, children } => {
+                    for (&index, (digest, proof)) in children {
+                        let mut child_path = path.clone();
+                        child_path.push(index as u8);
+                        if let Some(child_commit) = updated.get(&child_path) {
+                            changes.push((index, *digest, digest_commit::<V::Fr, H>(child_commit), proof));
+                        }
+                    }
+                    commit
+                }
+                PartialNode::Extension { commit, stem, slots } => {
+                    for (&index, slot) in slots {
+                        if let Some(write) = &slot.write {
+                            let old = digest_slot::<V::Fr, H>(&join_key(stem, index as u8), &slot.value);
+                            let new = digest_slot::<V::Fr, H>(&join_key(stem, index as u8), &write.0);
+                            changes.push((index, old, new, &slot.proof));
+                        }
+                    }
+                    commit
+                }
+            };
+            if !changes.is_empty() {
+                let new_commit = self.vc.update_at(commit, &changes).ok_or(PartialTreeError::UpdateRejected)?;
+                updated.insert(path.clone(), new_commit);
+            }
+        }
+
+        Ok(updated.remove(&Vec::new()).unwrap_or_else(|| self.root.clone()))
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    tree::VerkleTree,
+    vc::{VectorCommitment, VerkleProof, ARITY},
+    Value,
+};
+
+/// A [`VerkleTree`] whose paths are hashes of the user's keys.
+///
+/// Hashing spreads stems uniformly, so callers cannot choose keys with long shared prefixes that
+/// would force deep single-child chains of Internal nodes. Each user key is hashed with `H` and
+/// the digest is the tree key. Proofs are over the hashed key, which verifiers derive with
+/// [`SecureVerkleTree::tree_key`].
+///
+/// The tree itself only holds hashed keys. When built with [`SecureVerkleTree::with_preimages`]
+/// it also remembers the original key of every insert, so iteration can report it.
+pub struct SecureVerkleTree<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    tree: VerkleTree<V, H, W>,
+    preimages: Option<HashMap<[u8; 32], Vec<u8>>>, // tree key -> user key
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> SecureVerkleTree<V, H, W> {
+    pub fn new(vc: V) -> Self {
+        SecureVerkleTree { tree: VerkleTree::new(vc), preimages: None }
+    }
+
+    /// Like [`SecureVerkleTree::new`], but keeps the original key of every insert.
+    pub fn with_preimages(vc: V) -> Self {
+        SecureVerkleTree { tree: VerkleTree::new(vc), preimages: Some(HashMap::new()) }
+    }
+
+    /// The key under which `key` is stored in the underlying tree.
+    pub fn tree_key(key: &[u8]) -> [u8; 32] {
+        H::hash(key)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<Value> {
+        self.tree.get(Self::tree_key(key))
+    }
+
+    pub fn insert(&mut self, key: &[u8], value: Value) {
+        let tree_key = Self::tree_key(key);
+        if let Some(preimages) = &mut self.preimages {
+            preimages.insert(tree_key, key.to_vec());
+        }
+        self.tree.insert(tree_key, value);
+    }
+
+    pub fn commit(&mut self) -> V::Commitment {
+        self.tree.commit()
+    }
+
+    /// Proves `key` in the underlying tree; verify it against [`SecureVerkleTree::tree_key`].
+    pub fn prove_get(&self, key: &[u8]) -> Option<VerkleProof<V, H, W>> {
+        self.tree.prove_get(Self::tree_key(key))
+    }
+
+    /// The original key stored under `tree_key`, if preimages are kept.
+    pub fn preimage(&self, tree_key: &[u8; 32]) -> Option<&[u8]> {
+        self.preimages.as_ref()?.get(tree_key).map(|k| &k[..])
+    }
+
+    /// Iterates over `(tree key, original key, value)` in tree key order. The original key is
+    /// `None` unless the tree keeps preimages.
+    pub fn iter(&self) -> impl Iterator<Item = ([u8; 32], Option<&[u8]>, &Value)> + '_ {
+        self.tree.iter().map(|(k, v)| (k, self.preimage(&k), v))
+    }
+
+    /// The underlying tree over hashed keys.
+    pub fn inner(&self) -> &VerkleTree<V, H, W> {
+        &self.tree
+    }
+}
@@ -0,0 +1,372 @@
+use std::{fmt, marker::PhantomData, slice};
+
+use bytes::Bytes;
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    node::{join_key, split_key, stem_digit, stem_digits, Node},
+    tree::VerkleTree,
+    utils::{digest_commit, digest_slot, ZERO_CHILD, ZERO_VALUE},
+    vc::{VectorCommitment, ARITY},
+    Value,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapError {
+    /// An opening in the chunk does not verify, or a node does not link to its parent or to the
+    /// expected root.
+    InvalidProof,
+    /// The chunk leaves a child or slot in its range unopened, so entries could be missing.
+    Gap,
+    /// The chunk's range is empty, or it does not start where it was requested.
+    InvalidRange,
+    /// Parts of the key space were never imported, see [`SnapImporter::missing`].
+    Incomplete,
+    /// The imported state does not commit to the expected root.
+    RootMismatch,
+    /// The exported tree changed since its last `commit()`, so its cached digests cannot be
+    /// opened.
+    Uncommitted,
+}
+
+impl fmt::Display for SnapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapError::InvalidProof => write!(f, "chunk proof does not verify against the expected root"),
+            SnapError::Gap => write!(f, "chunk does not open everything in its range"),
+            SnapError::InvalidRange => write!(f, "chunk range is empty or not the requested one"),
+            SnapError::Incomplete => write!(f, "parts of the key space were never imported"),
+            SnapError::RootMismatch => write!(f, "imported state does not match the expected root"),
+            SnapError::Uncommitted => write!(f, "exported tree changed since its last commit"),
+        }
+    }
+}
+
+impl std::error::Error for SnapError {}
+
+/// A node of the exported tree together with openings of the children or slots that a
+/// [`SnapChunk`] needs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProvenNode<V: VectorCommitment<W>, const W: usize = ARITY> {
+    Internal {
+        commit: V::Commitment,
+        children: Vec<(usize, V::Fr, V::Proof)>, // (index, child digest, opening), ascending
+    },
+    Extension {
+        commit: V::Commitment,
+        stem: [u8; 32], // low log2(W) bits clear, see `split_key`
+        slots: Vec<(u8, Option<Bytes>, V::Proof)>, // (suffix, value or None if empty, opening), ascending
+    },
+}
+
+/// The entries with keys in `[start, next)`, proven complete against the root they were exported
+/// from.
+///
+/// `nodes` is the part of the tree that can hold keys of the range, in pre-order: each Internal
+/// is followed by the nodes of its opened children that are not empty. Every child and slot that
+/// may hold a key of the range is opened, empty ones included, so a chunk cannot leave out a
+/// stem or a slot. Each Extension also opens at least one occupied slot, which binds its stem.
+pub struct SnapChunk<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    pub start: [u8; 32],
+    pub next: Option<[u8; 32]>, // end of the range and key to resume from, None once the tree is exhausted
+    pub nodes: Vec<ProvenNode<V, W>>,
+    pub(crate) hasher: PhantomData<H>,
+}
+
+// Whether the subtree reached through the stem digits `path` may hold keys of [start, next).
+// Digit prefixes are compared, so a subtree that starts exactly at `next` also counts.
+fn subtree_in_range<const W: usize>(path: &[usize], start: &[u8; 32], next: Option<&[u8; 32]>) -> bool {
+    let digits = |key: &[u8; 32]| {
+        let (stem, _) = split_key::<W>(*key);
+        (0..path.len()).map(move |level| stem_digit::<W>(&stem, level))
+    };
+    path.iter().copied().ge(digits(start)) && next.is_none_or(|next| path.iter().copied().le(digits(next)))
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> SnapChunk<V, H, W> {
+    fn in_range(&self, key: &[u8; 32]) -> bool {
+        *key >= self.start && self.next.is_none_or(|next| *key < next)
+    }
+
+    /// The entries in the chunk's range, as claimed by its proofs.
+    pub fn entries(&self) -> impl Iterator<Item = ([u8; 32], Value)> + '_ {
+        let extensions = self.nodes.iter().filter_map(|node| match node {
+            ProvenNode::Extension { stem, slots, .. } => Some((stem, slots)),
+            ProvenNode::Internal { .. } => None,
+        });
+        extensions.flat_map(move |(stem, slots)| {
+            slots.iter().filter_map(move |(suf, value, _)| {
+                let key = join_key(stem, *suf);
+                value.clone().filter(|_| self.in_range(&key)).map(|v| (key, Value(v)))
+            })
+        })
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Exports the entries from `start` onwards, stopping once `max_bytes` of keys and values
+    /// have been emitted. At least one entry is exported if any remain. Returns
+    /// [`SnapError::Uncommitted`] if the tree changed since its last commit.
+    pub fn export_range(&self, start: [u8; 32], max_bytes: usize) -> Result<SnapChunk<V, H, W>, SnapError> {
+        if self.dirty {
+            return Err(SnapError::Uncommitted);
+        }
+        let mut size = 0;
+        let mut next = None;
+        for (key, value) in self.iter_from(start) {
+            if size > 0 && size + key.len() + value.0.len() > max_bytes {
+                next = Some(key);
+                break;
+            }
+            size += key.len() + value.0.len();
+        }
+
+        let mut chunk = SnapChunk { start, next, nodes: Vec::new(), hasher: PhantomData };
+        if let Some(root) = &self.root {
+            self.prove_range(root, &mut Vec::new(), &mut chunk);
+        }
+        Ok(chunk)
+    }
+
+    // Appends `node`, reached through the stem digits `path`, and the nodes below it that can
+    // hold keys of the chunk's range.
+    fn prove_range(&self, node: &Node<V, W>, path: &mut Vec<usize>, chunk: &mut SnapChunk<V, H, W>) {
+        match node {
+            Node::Internal { children, commitments } => {
+                let opened: Vec<usize> = (0..W)
+                    .filter(|&index| {
+                        path.push(index);
+                        let in_range = subtree_in_range::<W>(path, &chunk.start, chunk.next.as_ref());
+                        path.pop();
+                        in_range
+                    })
+                    .collect();
+                chunk.nodes.push(ProvenNode::Internal {
+                    commit: self.vc.commit_from_children(commitments),
+                    children: opened.iter().map(|&index| (index, commitments[index], self.vc.open_at(commitments, index).1)).collect(),
+                });
+                for index in opened {
+                    if let Some(child) = &children[index] {
+                        path.push(index);
+                        self.prove_range(child, path, chunk);
+                        path.pop();
+                    }
+                }
+            }
+            Node::Extension { stem, slots, slot_commitment } => {
+                let mut opened: Vec<usize> = (0..W).filter(|&suf| chunk.in_range(&join_key(stem, suf as u8))).collect();
+                // Empty openings alone would fit any stem, so open a stored value as well
+                if opened.iter().all(|&suf| slots[suf].is_none()) {
+                    let occupied = (0..W).find(|&suf| slots[suf].is_some()).expect("Extensions are never empty");
+                    opened.push(occupied);
+                    opened.sort_unstable();
+                }
+                chunk.nodes.push(ProvenNode::Extension {
+                    commit: self.vc.commit_from_children(slot_commitment),
+                    stem: *stem,
+                    slots: opened
+                        .into_iter()
+                        .map(|suf| (suf as u8, slots[suf].as_ref().map(|v| v.0.clone()), self.vc.open_at(slot_commitment, suf).1))
+                        .collect(),
+                });
+            }
+        }
+    }
+}
+
+/// Rebuilds a tree from [`SnapChunk`]s, checking every chunk against a trusted root.
+///
+/// The importer records which key ranges its chunks covered. Chunks may arrive in any order and
+/// from different peers; whatever was skipped is listed by [`SnapImporter::missing`] and can be
+/// requested again, see [`SnapImporter::heal`].
+pub struct SnapImporter<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    vc: V,
+    root: V::Commitment,
+    tree: VerkleTree<V, H, W>,
+    covered: Vec<([u8; 32], Option<[u8; 32]>)>, // disjoint [start, end) ranges in key order, None ends at the last key
+}
+
+impl<V: VectorCommitment<W> + Clone, H: TreeHasher, const W: usize> SnapImporter<V, H, W> {
+    pub fn new(vc: V, root: V::Commitment) -> Self {
+        SnapImporter { tree: VerkleTree::new(vc.clone()), vc, root, covered: Vec::new() }
+    }
+
+    /// Verifies `chunk` against the expected root and adds its entries. Returns the number of
+    /// entries added; nothing is added unless the chunk proves every entry of its range.
+    pub fn import_chunk(&mut self, chunk: &SnapChunk<V, H, W>) -> Result<usize, SnapError> {
+        if chunk.next.is_some_and(|next| next <= chunk.start) {
+            return Err(SnapError::InvalidRange);
+        }
+
+        let mut entries = Vec::new();
+        let mut nodes = chunk.nodes.iter();
+        if chunk.nodes.is_empty() {
+            // Only an empty tree has no nodes to open
+            if self.root != V::Commitment::default() {
+                return Err(SnapError::Gap);
+            }
+        } else {
+            self.verify_node(&mut nodes, None, &mut Vec::new(), chunk, &mut entries)?;
+        }
+        if nodes.next().is_some() {
+            return Err(SnapError::InvalidProof);
+        }
+
+        let added = entries.len();
+        for (key, value) in entries {
+            self.tree.insert(key, value);
+        }
+        self.cover(chunk.start, chunk.next);
+        Ok(added)
+    }
+
+    // Checks the next node of `nodes`, reached through the stem digits `path`, and collects the
+    // entries of the chunk's range below it. `digest` is what its commitment must hash to, or
+    // None for the root.
+    fn verify_node(
+        &self,
+        nodes: &mut slice::Iter<'_, ProvenNode<V, W>>,
+        digest: Option<V::Fr>,
+        path: &mut Vec<usize>,
+        chunk: &SnapChunk<V, H, W>,
+        entries: &mut Vec<([u8; 32], Value)>,
+    ) -> Result<(), SnapError> {
+        // An opened child that is not empty must be followed by its node
+        let node = nodes.next().ok_or(SnapError::Gap)?;
+        let (ProvenNode::Internal { commit, .. } | ProvenNode::Extension { commit, .. }) = node;
+        let linked = match digest {
+            None => *commit == self.root,
+            Some(digest) => digest_commit::<V::Fr, H>(commit) == digest,
+        };
+        if !linked {
+            return Err(SnapError::InvalidProof);
+        }
+
+        match node {
+            ProvenNode::Internal { commit, children } => {
+                if path.len() == stem_digits::<W>() {
+                    return Err(SnapError::InvalidProof); // every stem digit is already consumed
+                }
+                let mut opened = children.iter().peekable();
+                for index in 0..W {
+                    path.push(index);
+                    let required = subtree_in_range::<W>(path, &chunk.start, chunk.next.as_ref());
+                    match opened.next_if(|(i, ..)| *i == index) {
+                        None if required => return Err(SnapError::Gap),
+                        None => {}
+                        Some((_, child_digest, proof)) => {
+                            if !self.vc.verify_at(commit, index, *child_digest, proof) {
+                                return Err(SnapError::InvalidProof);
+                            }
+                            if *child_digest != ZERO_CHILD::<V::Fr, H>() {
+                                self.verify_node(nodes, Some(*child_digest), path, chunk, entries)?;
+                            }
+                        }
+                    }
+                    path.pop();
+                }
+                // Left over openings are out of order or beyond the width
+                if opened.next().is_some() {
+                    return Err(SnapError::InvalidProof);
+                }
+            }
+            ProvenNode::Extension { commit, stem, slots } => {
+                // The stem must continue the path that led here and have no suffix bits set
+                if split_key::<W>(*stem).0 != *stem || path.iter().enumerate().any(|(level, &digit)| stem_digit::<W>(stem, level) != digit) {
+                    return Err(SnapError::InvalidProof);
+                }
+                let mut opened = slots.iter().peekable();
+                let mut bound = false;
+                for suf in 0..W {
+                    let key = join_key(stem, suf as u8);
+                    match opened.next_if(|(s, ..)| *s as usize == suf) {
+                        None if chunk.in_range(&key) => return Err(SnapError::Gap),
+                        None => {}
+                        Some((_, value, proof)) => {
+                            let value_digest = match value {
+                                Some(v) => digest_slot::<V::Fr, H>(&key, v),
+                                None => ZERO_VALUE::<V::Fr, H>(),
+                            };
+                            if !self.vc.verify_at(commit, suf, value_digest, proof) {
+                                return Err(SnapError::InvalidProof);
+                            }
+                            if let Some(v) = value {
+                                bound = true;
+                                if chunk.in_range(&key) {
+                                    entries.push((key, Value(v.clone())));
+                                }
+                            }
+                        }
+                    }
+                }
+                // Only an occupied slot ties the openings to this stem
+                if opened.next().is_some() || !bound {
+                    return Err(SnapError::InvalidProof);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Records [start, end) as imported, merging it with the ranges it touches.
+    fn cover(&mut self, start: [u8; 32], end: Option<[u8; 32]>) {
+        self.covered.push((start, end));
+        self.covered.sort_by_key(|(start, _)| *start);
+        let mut merged: Vec<([u8; 32], Option<[u8; 32]>)> = Vec::new();
+        for (start, end) in self.covered.drain(..) {
+            match merged.last_mut() {
+                Some((_, last_end)) if last_end.is_none_or(|e| start <= e) => {
+                    *last_end = last_end.zip(end).map(|(a, b)| a.max(b));
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        self.covered = merged;
+    }
+
+    /// Key ranges `[start, end)` that no imported chunk covered yet, in key order. An `end` of
+    /// None extends to the last key.
+    pub fn missing(&self) -> Vec<([u8; 32], Option<[u8; 32]>)> {
+        let mut gaps = Vec::new();
+        let mut cursor = Some([0u8; 32]);
+        for &(start, end) in &self.covered {
+            match cursor {
+                Some(from) if from < start => gaps.push((from, Some(start))),
+                None => break,
+                _ => {}
+            }
+            cursor = cursor.zip(end).map(|(a, b)| a.max(b));
+        }
+        if let Some(from) = cursor {
+            gaps.push((from, None));
+        }
+        gaps
+    }
+
+    /// Healing pass: requests the start of each missing range from `fetch`, which returns a chunk
+    /// exported from that key by any peer, and imports it, until nothing is missing. Returns the
+    /// number of entries healed. Fails on the first error from `fetch`, or the first chunk that
+    /// does not verify or does not start at the requested key.
+    pub fn heal(&mut self, mut fetch: impl FnMut([u8; 32]) -> Result<SnapChunk<V, H, W>, SnapError>) -> Result<usize, SnapError> {
+        let mut healed = 0;
+        while let Some(&(start, _)) = self.missing().first() {
+            let chunk = fetch(start)?;
+            if chunk.start != start {
+                return Err(SnapError::InvalidRange);
+            }
+            healed += self.import_chunk(&chunk)?;
+        }
+        Ok(healed)
+    }
+
+    /// Returns the imported tree once every range is covered and it commits to the expected root.
+    pub fn finish(mut self) -> Result<VerkleTree<V, H, W>, SnapError> {
+        if !self.missing().is_empty() {
+            return Err(SnapError::Incomplete);
+        }
+        if self.tree.commit() != self.root {
+            return Err(SnapError::RootMismatch);
+        }
+        Ok(self.tree)
+    }
+}
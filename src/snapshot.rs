@@ -0,0 +1,288 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    sync::Arc,
+};
+
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+
+use crate::{
+    hasher::TreeHasher,
+    node::{empty_digests, split_key, stem_digits, stem_len, Node},
+    tree::VerkleTree,
+    vc::{cached_commitment, VectorCommitment},
+    Value,
+};
+
+const MAGIC: &[u8; 4] = b"VRKS";
+const FORMAT_VERSION: u8 = 1;
+const WITH_CACHES: u8 = 1;
+const INTERNAL: u8 = 0;
+const EXTENSION: u8 = 1;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The input is not a snapshot, or is damaged.
+    Format(&'static str),
+    /// The snapshot was written with another format version, width or hash function.
+    Incompatible(&'static str),
+    /// The root in the header differs from the loaded tree's root. For a snapshot with caches
+    /// that root comes from the root node's cached digests alone, so this catches a snapshot
+    /// loaded with another setup, not tampered nodes.
+    RootMismatch,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot access failed: {e}"),
+            SnapshotError::Format(reason) => write!(f, "invalid snapshot: {reason}"),
+            SnapshotError::Incompatible(what) => write!(f, "snapshot has a different {what}"),
+            SnapshotError::RootMismatch => write!(f, "loaded tree does not match the snapshot root"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+
+// Lengths come from the input, so the buffer grows with the bytes actually read rather than
+// being allocated up front.
+fn read_bytes(r: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut b = Vec::new();
+    r.by_ref().take(len as u64).read_to_end(&mut b)?;
+    if b.len() < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(b)
+}
+
+// One bit per child or slot, least significant bit first.
+fn write_bitmap<T>(w: &mut impl Write, items: &[Option<T>]) -> io::Result<()> {
+    let mut bitmap = vec![0u8; items.len().div_ceil(8)];
+    for (i, item) in items.iter().enumerate() {
+        if item.is_some() {
+            bitmap[i / 8] |= 1 << (i % 8);
+        }
+    }
+    w.write_all(&bitmap)
+}
+
+fn read_bitmap<const W: usize>(r: &mut impl Read) -> io::Result<[bool; W]> {
+    let bitmap = read_bytes(r, W.div_ceil(8))?;
+    Ok(std::array::from_fn(|i| bitmap[i / 8] & (1 << (i % 8)) != 0))
+}
+
+fn write_digests<F: CanonicalSerialize>(w: &mut impl Write, digests: &[F]) -> io::Result<()> {
+    for digest in digests {
+        digest.serialize_compressed(&mut *w).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+fn read_digests<F: CanonicalDeserialize, const W: usize>(r: &mut impl Read) -> Result<[F; W], SnapshotError> {
+    let digests: Vec<F> = (0..W)
+        .map(|_| {
+            F::deserialize_compressed(&mut *r).map_err(|e| match e {
+                SerializationError::IoError(e) => SnapshotError::Io(e),
+                _ => SnapshotError::Format("invalid digest"),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(digests.try_into().unwrap_or_else(|_| unreachable!("W digests read")))
+}
+
+fn write_node<V: VectorCommitment<W>, const W: usize>(w: &mut impl Write, node: &Node<V, W>, caches: bool) -> io::Result<()> {
+    match node {
+        Node::Internal { children, commitments } => {
+            w.write_all(&[INTERNAL])?;
+            write_bitmap(w, children)?;
+            if caches {
+                write_digests(w, commitments)?;
+            }
+            for child in children.iter().flatten() {
+                write_node(w, child, caches)?;
+            }
+        }
+        Node::Extension { stem, slots, slot_commitment } => {
+            w.write_all(&[EXTENSION])?;
+            w.write_all(&stem[..stem_len::<W>()])?;
+            write_bitmap(w, slots)?;
+            for value in slots.iter().flatten() {
+                w.write_all(&(value.0.len() as u32).to_le_bytes())?;
+                w.write_all(&value.0)?;
+            }
+            if caches {
+                write_digests(w, slot_commitment)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Reads the node `depth` digits below the root without its children. Returns it together with
+// the indices of the children that follow it, in order.
+fn read_node<V: VectorCommitment<W>, const W: usize>(r: &mut impl Read, depth: usize, caches: bool) -> Result<(Node<V, W>, Vec<usize>), SnapshotError> {
+    match read_u8(r)? {
+        INTERNAL => {
+            if depth >= stem_digits::<W>() {
+                return Err(SnapshotError::Format("Internal node below the full stem depth"));
+            }
+            let occupied = read_bitmap::<W>(r)?;
+            let commitments = if caches { read_digests::<V::Fr, W>(r)? } else { empty_digests::<V, W>() };
+            let children = std::array::from_fn(|_| None);
+            let pending = (0..W).filter(|&i| occupied[i]).collect();
+            Ok((Node::Internal { children, commitments }, pending))
+        }
+        EXTENSION => {
+            let mut stem = [0u8; 32];
+            stem[..stem_len::<W>()].copy_from_slice(&read_bytes(r, stem_len::<W>())?);
+            if split_key::<W>(stem).0 != stem {
+                return Err(SnapshotError::Format("stem has suffix bits set"));
+            }
+            let occupied = read_bitmap::<W>(r)?;
+            let mut slots: [Option<Value>; W] = std::array::from_fn(|_| None);
+            for (slot, _) in slots.iter_mut().zip(occupied).filter(|(_, o)| *o) {
+                let len = read_u32(r)? as usize;
+                *slot = Some(Value::from(read_bytes(r, len)?));
+            }
+            let slot_commitment = if caches { read_digests::<V::Fr, W>(r)? } else { empty_digests::<V, W>() };
+            Ok((Node::Extension { stem, slots, slot_commitment }, Vec::new()))
+        }
+        _ => Err(SnapshotError::Format("unknown node tag")),
+    }
+}
+
+// Reads a node and its subtree. Iterative, as every level of a recursive reader would keep a
+// whole node on the stack, and a chain of Internal nodes can be a full stem deep.
+fn read_subtree<V: VectorCommitment<W>, const W: usize>(r: &mut impl Read, caches: bool) -> Result<Node<V, W>, SnapshotError> {
+    // Internal nodes still reading their children, from the root down, each with the indices of
+    // the children left, last one first
+    let mut open: Vec<(Box<Node<V, W>>, Vec<usize>)> = Vec::new();
+    loop {
+        let (node, mut pending) = read_node::<V, W>(r, open.len(), caches)?;
+        if !pending.is_empty() {
+            pending.reverse();
+            open.push((Box::new(node), pending));
+            continue;
+        }
+        // Hand finished nodes to their parents until one still expects children
+        let mut done = node;
+        loop {
+            let Some((parent, pending)) = open.last_mut() else { return Ok(done) };
+            let Node::Internal { children, .. } = &mut **parent else { unreachable!("only Internal nodes have children") };
+            children[pending.pop().expect("open nodes expect a child")] = Some(Arc::new(done));
+            if !pending.is_empty() {
+                break;
+            }
+            done = *open.pop().expect("parent is open").0;
+        }
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Commits the tree and writes it as a single snapshot: a header, then every node in
+    /// depth-first order with its stem and slot values. Stems are stored without the bytes that
+    /// only hold suffix bits, so a width-256 stem takes 31 bytes. With `caches`, each node also carries its
+    /// cached child or slot digests, so [`VerkleTree::read_snapshot`] does not have to recompute
+    /// them.
+    ///
+    /// The header is `"VRKS" || format version || width (u16 LE) || hasher ID || scheme version
+    /// || flags || root length (u32 LE) || compressed root`. Recorded versions are not written.
+    pub fn write_snapshot(&mut self, mut writer: impl Write, caches: bool) -> io::Result<V::Commitment> {
+        let root = self.commit();
+        let mut root_bytes = Vec::new();
+        root.serialize_compressed(&mut root_bytes).expect("serialize commitment");
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        writer.write_all(&(W as u16).to_le_bytes())?;
+        writer.write_all(&[H::ID, H::SCHEME_VERSION, if caches { WITH_CACHES } else { 0 }])?;
+        writer.write_all(&(root_bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&root_bytes)?;
+
+        match &self.root {
+            Some(node) => {
+                writer.write_all(&[1])?;
+                write_node(&mut writer, node, caches)?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+        writer.flush()?;
+        Ok(root)
+    }
+
+    /// Loads a snapshot written by [`VerkleTree::write_snapshot`] with the same width and hasher.
+    ///
+    /// A snapshot without caches is committed in full and checked against the root in the
+    /// header.
+    ///
+    /// A snapshot with caches is NOT verified. Its digests are taken as they are and only the
+    /// root node's digests are checked against the header, so loading costs a single commitment.
+    /// Values or digests changed anywhere below the root go unnoticed, and proofs served from
+    /// such a tree can be wrong. Only load snapshots with caches from a trusted source; calling
+    /// `commit()` afterwards recomputes every digest and returns the actual root.
+    pub fn read_snapshot(mut reader: impl Read, vc: V) -> Result<Self, SnapshotError> {
+        if read_bytes(&mut reader, MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::Format("missing snapshot magic"));
+        }
+        if read_u8(&mut reader)? != FORMAT_VERSION {
+            return Err(SnapshotError::Incompatible("format version"));
+        }
+        let mut width = [0u8; 2];
+        reader.read_exact(&mut width)?;
+        if u16::from_le_bytes(width) as usize != W {
+            return Err(SnapshotError::Incompatible("width"));
+        }
+        if read_u8(&mut reader)? != H::ID || read_u8(&mut reader)? != H::SCHEME_VERSION {
+            return Err(SnapshotError::Incompatible("hash function"));
+        }
+        let caches = match read_u8(&mut reader)? {
+            0 => false,
+            WITH_CACHES => true,
+            _ => return Err(SnapshotError::Format("unknown flags")),
+        };
+        let len = read_u32(&mut reader)? as usize;
+        let root_bytes = read_bytes(&mut reader, len)?;
+
+        let mut tree = VerkleTree::new(vc);
+        tree.root = match read_u8(&mut reader)? {
+            0 => None,
+            1 => Some(read_subtree(&mut reader, caches)?),
+            _ => return Err(SnapshotError::Format("invalid root marker")),
+        };
+        // Inserts rely on the shape, so a misplaced stem must not get in
+        if !tree.is_canonical() {
+            return Err(SnapshotError::Format("tree shape is not canonical"));
+        }
+
+        let root = match (&tree.root, caches) {
+            (Some(node), true) => cached_commitment(&tree.vc, node),
+            _ => tree.commit(),
+        };
+        let mut committed = Vec::new();
+        root.serialize_compressed(&mut committed).expect("serialize commitment");
+        if committed != root_bytes {
+            return Err(SnapshotError::RootMismatch);
+        }
+        Ok(tree)
+    }
+}
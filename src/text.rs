@@ -0,0 +1,150 @@
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use ark_serialize::CanonicalSerialize;
+
+use crate::{
+    hasher::TreeHasher,
+    tree::VerkleTree,
+    utils::{decode_hex, encode_hex},
+    vc::VectorCommitment,
+    Value,
+};
+
+/// Line-oriented text formats for whole trees. Keys, values and the root are 0x-prefixed hex.
+///
+/// - `Csv`: a `key,value` header, one `key,value` line per entry and a `root,<commitment>`
+///   trailer.
+/// - `JsonLines`: one `{"key": .., "value": ..}` object per line and a `{"root": ..}` trailer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    Csv,
+    JsonLines,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    /// Line `line` (1-based) is not a valid entry or trailer.
+    Parse { line: usize, reason: &'static str },
+    /// The tree does not commit to the root in the trailer.
+    RootMismatch,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "read failed: {e}"),
+            ImportError::Parse { line, reason } => write!(f, "line {line}: {reason}"),
+            ImportError::RootMismatch => write!(f, "imported tree does not match the trailer root"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+enum Record {
+    Entry([u8; 32], Value),
+    Root(Vec<u8>),
+}
+
+fn parse_record(format: TextFormat, line: &str) -> Result<Option<Record>, &'static str> {
+    match format {
+        TextFormat::Csv => {
+            if line == "key,value" {
+                return Ok(None);
+            }
+            let (name, value) = line.split_once(',').ok_or("expected two comma-separated fields")?;
+            decode_record(name, value).map(Some)
+        }
+        TextFormat::JsonLines => {
+            let record: serde_json::Value = serde_json::from_str(line).map_err(|_| "invalid JSON")?;
+            let field = |name: &str| record.get(name).map(|v| v.as_str().ok_or("expected a hex string"));
+            match (field("key"), field("value"), field("root")) {
+                (Some(key), Some(value), None) => decode_record(key?, value?).map(Some),
+                (None, None, Some(root)) => decode_record("root", root?).map(Some),
+                _ => Err("expected key and value, or root"),
+            }
+        }
+    }
+}
+
+// `name` is either a hex key or `root`.
+fn decode_record(name: &str, value: &str) -> Result<Record, &'static str> {
+    let value = decode_hex(value.trim()).ok_or("invalid hex")?;
+    match name.trim() {
+        "root" => Ok(Record::Root(value)),
+        key => {
+            let key = decode_hex(key).and_then(|k| <[u8; 32]>::try_from(k).ok()).ok_or("key is not 32 bytes of hex")?;
+            Ok(Record::Entry(key, Value::from(value)))
+        }
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Inserts the entries read from `reader`, one line at a time, and returns how many were
+    /// read. If the input ends with a root trailer, the tree is committed and must match it.
+    pub fn import_text(&mut self, reader: impl BufRead, format: TextFormat) -> Result<usize, ImportError> {
+        let mut entries = 0;
+        let mut root = None;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let parse_error = |reason| ImportError::Parse { line: i + 1, reason };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if root.is_some() {
+                return Err(parse_error("data after the root trailer"));
+            }
+            match parse_record(format, &line).map_err(parse_error)? {
+                Some(Record::Entry(key, value)) => {
+                    self.insert(key, value);
+                    entries += 1;
+                }
+                Some(Record::Root(r)) => root = Some(r),
+                None => {}
+            }
+        }
+
+        if let Some(root) = root {
+            let mut committed = Vec::new();
+            self.commit().serialize_compressed(&mut committed).expect("serialize commitment");
+            if committed != root {
+                return Err(ImportError::RootMismatch);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Commits the tree and writes every entry in key order, followed by the root trailer.
+    pub fn export_text(&mut self, mut writer: impl Write, format: TextFormat) -> io::Result<V::Commitment> {
+        let root = self.commit();
+        let mut root_bytes = Vec::new();
+        root.serialize_compressed(&mut root_bytes).expect("serialize commitment");
+
+        if format == TextFormat::Csv {
+            writeln!(writer, "key,value")?;
+        }
+        for (key, value) in self.iter() {
+            match format {
+                TextFormat::Csv => writeln!(writer, "{},{}", encode_hex(&key), encode_hex(&value.0))?,
+                TextFormat::JsonLines => writeln!(writer, r#"{{"key":"{}","value":"{}"}}"#, encode_hex(&key), encode_hex(&value.0))?,
+            }
+        }
+        match format {
+            TextFormat::Csv => writeln!(writer, "root,{}", encode_hex(&root_bytes))?,
+            TextFormat::JsonLines => writeln!(writer, r#"{{"root":"{}"}}"#, encode_hex(&root_bytes))?,
+        }
+        writer.flush()?;
+        Ok(root)
+    }
+}
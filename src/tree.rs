@@ -1,230 +1,404 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+    sync::Arc,
+};
+
 use crate::{
-    node::{split_extension, split_key, ExtensionNode, Node}, utils::{digest_slot, ZERO_VALUE}, vc::{compute_commitment, Step, VectorCommitment, VerkleProof}, Value
+    hasher::{Blake3Hasher, TreeHasher}, node::{canonical_stems, empty_digests, join_key, split_extension, split_key, stem_digit, stem_digits, ExtensionNode, Node, Stem}, utils::digest_slot, vc::{compute_commitment, StemProof, Step, VectorCommitment, VerkleProof, ARITY}, version::Version, Value
 };
 
-pub struct VerkleTree<V: VectorCommitment> {
-    pub(crate) root: Option<Node<V>>,
-    vc: V,
+/// A verkle tree keyed by 32-byte keys. The low log2(W) bits of a key are its suffix, which
+/// picks a slot of an Extension, and the remaining bits are its stem.
+///
+/// Every node has W children or slots. Internal nodes consume the stem one log2(W)-bit digit per
+/// level, most significant bits first, so the default W = 256 uses a 31-byte stem and consumes
+/// one stem byte per level.
+///
+/// The tree shape is canonical: it depends only on the set of stored stems, never on the order
+/// of `insert` calls. An Extension for a stem sits one digit below the longest prefix it shares
+/// with any other stem (or at the root if it is the only stem), and an Internal node exists
+/// exactly for each prefix shared by at least two stems. `insert` preserves this because a new
+/// stem either lands in an empty child slot, which is the first digit where it differs from every
+/// stored stem, or meets a lone Extension that `split_extension` pushes down to the first digit
+/// where the two stems differ. Since commitments are a function of the shape and the slot
+/// values, `commit()` gives the same root for any permutation of the same inserts.
+///
+/// `H` selects the hash function behind all digests; proofs carry it in their type.
+pub struct VerkleTree<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    pub(crate) root: Option<Node<V, W>>,
+    pub(crate) versions: BTreeMap<u64, Version<V, W>>, // see `commit_version`
+    pub(crate) vc: V,
+    pub(crate) dirty: bool, // changed since the last commit, so cached digests are stale
+    hasher: PhantomData<H>,
 }
 
-impl<V: VectorCommitment> VerkleTree<V> {
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
     pub fn new(vc: V) -> Self {
-        VerkleTree { root: None, vc }
+        const { assert!(W.is_power_of_two() && W >= 2 && W <= 256, "width must be a power of two in 2..=256") };
+        VerkleTree { root: None, versions: BTreeMap::new(), vc, dirty: false, hasher: PhantomData }
+    }
+
+    /// Returns a handle to the value at `key`; cloning a [`Value`] does not copy its bytes.
+    pub fn get(&self, key: [u8; 32]) -> Option<Value> {
+        let (stem, suf) = split_key::<W>(key);
+        lookup(self.root.as_ref(), 0, &stem, suf)
+    }
+
+    pub fn insert(&mut self, key: [u8; 32], value: Value) {
+        let (stem, suf) = split_key::<W>(key);
+        insert_at(&mut self.root, 0, stem, suf, value);
+        self.dirty = true;
     }
 
-    pub fn get(&self, key: [u8; 32]) -> Option<&Value> {
-        let (stem, suf) = split_key(key);
+    /// Removes the value at `key` and returns it. Nodes left without stems are dropped and an
+    /// Internal left with a single Extension is replaced by it, so the shape stays canonical.
+    pub fn remove(&mut self, key: [u8; 32]) -> Option<Value> {
+        // Checked first, so that removing an absent key copies no shared nodes
+        self.get(key)?;
+        let (stem, suf) = split_key::<W>(key);
+        let root = self.root.as_mut().expect("key is stored");
+        let old = remove_at(root, 0, &stem, suf);
+        if is_empty_extension(root) {
+            self.root = None;
+        }
+        self.dirty = true;
+        Some(old)
+    }
+
+    /// Iterates over the stored entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, V, W> {
+        Iter::new(self.root.as_ref())
+    }
 
-        let mut node: Option<&Node<V>> = self.root.as_ref();
+    /// Iterates over the stored entries with keys at or after `start`, in ascending key order.
+    pub fn iter_from(&self, start: [u8; 32]) -> Iter<'_, V, W> {
+        let (stem, suf) = split_key::<W>(start);
+        let mut iter = Iter { stack: Vec::new(), ext: None, suffix: 0 };
+        let mut node = self.root.as_ref();
 
-        for i in 0..31 {
+        for level in 0..=stem_digits::<W>() {
             match node {
-                None => return None,
-                Some(Node::Internal { children, ..}) => {
-                    let idx = stem[i] as usize;
-                    node = children[idx].as_deref();
+                None => break,
+                Some(Node::Internal { children, .. }) => {
+                    let index = stem_digit::<W>(&stem, level);
+                    // Later siblings hold larger keys; deeper ones are pushed last so they pop first
+                    iter.stack.extend(children[index + 1..].iter().rev().flatten().map(|c| &**c));
+                    node = children[index].as_deref();
                 }
-                Some(Node::Extension {
-                    stem: node_stem,
-                    slots,
-                    ..
-                }) => {
-                    if *node_stem != stem {
-                        return None;
+                Some(Node::Extension { stem: node_stem, slots, .. }) => {
+                    if *node_stem >= stem {
+                        iter.ext = Some((node_stem, slots));
+                        // Within the start stem, skip the slots before the start suffix
+                        iter.suffix = if *node_stem == stem { suf as usize } else { 0 };
                     }
-                    return slots[suf as usize].as_ref();
+                    break;
                 }
             }
         }
+        iter
+    }
 
-        // If we have reached here, then check whether we hit an extension node
-        if let Some(Node::Extension {
-            stem: node_stem,
-            slots: node_slots,
-            ..
-        }) = node
-        {
-            if *node_stem == stem {
-                return node_slots[suf as usize].as_ref();
-            }
+    /// Inserts many entries at once; for a repeated key the last entry wins. Entries are sorted
+    /// first, so consecutive inserts walk mostly the same path.
+    pub fn insert_batch(&mut self, entries: impl IntoIterator<Item = ([u8; 32], Value)>) {
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        // Stable, so repeated keys keep their order
+        entries.sort_by_key(|(key, _)| *key);
+        for (key, value) in entries {
+            self.insert(key, value);
         }
-
-        None
     }
 
-    fn create_root(&mut self, key: [u8; 32], value: Value) {
-        let (stem, suf) = split_key(key);
-        let mut slots: [Option<Value>; 256] = std::array::from_fn(|_| None);
-        slots[suf as usize] = Some(value);
-        self.root = Some(Node::Extension { stem, slots, slot_commitment: std::array::from_fn(|_| ZERO_VALUE::<V>()) });
+    /// Returns true if the tree has the canonical shape described on [`VerkleTree`].
+    pub fn is_canonical(&self) -> bool {
+        self.root.as_ref().is_none_or(|n| canonical_stems(n, &mut Vec::new()).is_some())
     }
 
-    pub fn insert(&mut self, key: [u8; 32], value: Value) {
-        let (stem, suf) = split_key(key);
+    /// Returns true if the tree changed since its last `commit()`, so proofs and exports would
+    /// be taken against stale commitments.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
 
-        if self.root.is_none() {
-            self.create_root(key, value);
-            return;
+    pub fn commit(&mut self) -> V::Commitment {
+        debug_assert!(self.is_canonical(), "tree shape depends on insertion order");
+        self.dirty = false;
+        match self.root {
+            Some(ref mut n) => compute_commitment::<V, H, W>(&self.vc, n),
+            None => V::Commitment::default(),
         }
+    }
 
-        let mut node = self.root.as_mut().unwrap();
+    // Internal steps from `root` down to the Extension holding `stem`, and that Extension.
+    fn stem_path<'a>(&self, root: Option<&'a Node<V, W>>, stem: &Stem) -> Option<(Vec<Step<V, W>>, &'a Node<V, W>)> {
+        let mut node = root?;
+        let mut steps = Vec::new();
 
-        for i in 0..31 {
+        for level in 0..=stem_digits::<W>() {
             match node {
-                Node::Internal { children , .. } => {
-                    let idx = stem[i] as usize;
-                    if children[idx].is_none() {
-                        // Create a new extension node here
-                        let mut slots: [Option<Value>; 256] = std::array::from_fn(|_| None);
-                        slots[suf as usize] = Some(value);
-                        children[idx] = Some(Box::new(Node::Extension { stem, slots, slot_commitment: std::array::from_fn(|_| ZERO_VALUE::<V>())}));
-                        return;
-                    } else {
-                        // We iterate through
-                        node = children[idx].as_mut().unwrap();
-                    }
+                Node::Internal { children, commitments } if level < stem_digits::<W>() => {
+                    let index = stem_digit::<W>(stem, level);
+                    let parent_commit = self.vc.commit_from_children(commitments);
+                    let (child_digest, proof) = self.vc.open_at(commitments, index);
+
+                    assert_eq!(child_digest, commitments[index], "opening did not return correct value");
+
+                    steps.push(Step::Internal { parent_commit, index, child_digest, proof });
+                    node = children[index].as_deref()?;
                 }
-                Node::Extension {
-                    stem: node_stem,
-                    slots,
-                    ..
-                } => {
-                    if *node_stem != stem {
-                        let old_slots = std::mem::replace(slots, std::array::from_fn(|_| None));
-                        let old_node = ExtensionNode {
-                            stem: *node_stem,
-                            slots: old_slots,
-                        };
-                        // If the stems don't match, we need to split the node
-                        *node = split_extension(i, old_node, stem, suf, value);
-                        // We can return now that we have added the new extension node
-                        return;
-                    } else {
-                        // If the stems match, we can just insert the value
-                        slots[suf as usize] = Some(value);
-                        return;
-                    }
+                Node::Extension { stem: node_stem, .. } => return (node_stem == stem).then_some((steps, node)),
+                // Any other shape would be a construction bug
+                Node::Internal { .. } => break,
+            }
+        }
+        unreachable!("unexpected node at full stem depth")
+    }
+
+    pub fn prove_get(&self, key: [u8; 32]) -> Option<VerkleProof<V, H, W>> {
+        self.prove_get_in(self.root.as_ref(), key)
+    }
+
+    // Proof for `key` in the committed tree under `root`.
+    pub(crate) fn prove_get_in(&self, root: Option<&Node<V, W>>, key: [u8; 32]) -> Option<VerkleProof<V, H, W>> {
+        let (stem, suf) = split_key::<W>(key);
+
+        let (mut steps, ext) = self.stem_path(root, &stem)?;
+        let Node::Extension { slots, slot_commitment, .. } = ext else {
+            unreachable!("stem_path ends at an Extension")
+        };
+        let value = slots[suf as usize].clone()?.0;
+
+        let ext_commit = self.vc.commit_from_children(slot_commitment);
+        // Open at the suffix (suf), not a stem digit
+        let (slot_digest, proof) = self.vc.open_at(slot_commitment, suf as usize);
+        // Recompute expected digest binding key+value
+        let expected = digest_slot::<V::Fr, H>(&key, &value);
+        assert_eq!(slot_digest, expected, "slot digest mismatch (stem binding)");
+        steps.push(Step::Extension { ext_commit, index: suf as usize, proof });
+
+        Some(VerkleProof { steps, value, hasher: PhantomData })
+    }
+
+    /// Proves several slots of one stem at once, sharing the path and the Extension commitment.
+    /// Empty slots are proven empty. `stem` may be any key of the stem; its suffix bits are
+    /// ignored. Returns None if the stem is not stored or a suffix is not below W.
+    pub fn prove_stem(&self, stem: [u8; 32], suffixes: &[u8]) -> Option<StemProof<V, H, W>> {
+        let (stem, _) = split_key::<W>(stem);
+        let suffixes: BTreeSet<u8> = suffixes.iter().copied().collect();
+        if suffixes.iter().any(|&suf| suf as usize >= W) {
+            return None;
+        }
+
+        let (steps, ext) = self.stem_path(self.root.as_ref(), &stem)?;
+        let Node::Extension { slots, slot_commitment, .. } = ext else {
+            unreachable!("stem_path ends at an Extension")
+        };
+
+        let ext_commit = self.vc.commit_from_children(slot_commitment);
+        let slots = suffixes
+            .into_iter()
+            .map(|suf| {
+                let (_, proof) = self.vc.open_at(slot_commitment, suf as usize);
+                (suf, slots[suf as usize].as_ref().map(|v| v.0.clone()), proof)
+            })
+            .collect();
+
+        Some(StemProof { steps, ext_commit, slots, hasher: PhantomData })
+    }
+}
+
+// Value at (stem, suf) in the subtree `node`, which sits `depth` digits below the root.
+pub(crate) fn lookup<V: VectorCommitment<W>, const W: usize>(mut node: Option<&Node<V, W>>, depth: usize, stem: &Stem, suf: u8) -> Option<Value> {
+    for i in depth..stem_digits::<W>() {
+        match node {
+            None => return None,
+            Some(Node::Internal { children, ..}) => {
+                let idx = stem_digit::<W>(stem, i);
+                node = children[idx].as_deref();
+            }
+            Some(Node::Extension {
+                stem: node_stem,
+                slots,
+                ..
+            }) => {
+                if node_stem != stem {
+                    return None;
                 }
+                return slots[suf as usize].clone();
             }
         }
+    }
+
+    // If we have reached here, then check whether we hit an extension node
+    if let Some(Node::Extension {
+        stem: node_stem,
+        slots: node_slots,
+        ..
+    }) = node
+    {
+        if node_stem == stem {
+            return node_slots[suf as usize].clone();
+        }
+    }
 
+    None
+}
+
+// Stores (stem, suf) -> value in the subtree `root`, which sits `depth` digits below the root.
+pub(crate) fn insert_at<V: VectorCommitment<W>, const W: usize>(root: &mut Option<Node<V, W>>, depth: usize, stem: Stem, suf: u8, value: Value) {
+    let Some(mut node) = root.as_mut() else {
+        let mut slots: [Option<Value>; W] = std::array::from_fn(|_| None);
+        slots[suf as usize] = Some(value);
+        *root = Some(Node::Extension { stem, slots, slot_commitment: empty_digests::<V, W>() });
+        return;
+    };
+
+    for i in depth..stem_digits::<W>() {
         match node {
-            // Hit the stem bucket exactly here
+            Node::Internal { children , .. } => {
+                let idx = stem_digit::<W>(&stem, i);
+                if children[idx].is_none() {
+                    // Create a new extension node here
+                    let mut slots: [Option<Value>; W] = std::array::from_fn(|_| None);
+                    slots[suf as usize] = Some(value);
+                    children[idx] = Some(Arc::new(Node::Extension { stem, slots, slot_commitment: empty_digests::<V, W>()}));
+                    return;
+                } else {
+                    // We iterate through, copying the child if a recorded version still shares it
+                    node = Arc::make_mut(children[idx].as_mut().unwrap());
+                }
+            }
             Node::Extension {
                 stem: node_stem,
                 slots,
-                slot_commitment: _,
-            } if *node_stem == stem => {
-                slots[suf as usize] = Some(value);
-                return;
+                ..
+            } => {
+                if *node_stem != stem {
+                    let old_slots = std::mem::replace(slots, std::array::from_fn(|_| None));
+                    let old_node = ExtensionNode {
+                        stem: *node_stem,
+                        slots: old_slots,
+                    };
+                    // If the stems don't match, we need to split the node
+                    *node = split_extension(i, old_node, stem, suf, value);
+                    // We can return now that we have added the new extension node
+                    return;
+                } else {
+                    // If the stems match, we can just insert the value
+                    slots[suf as usize] = Some(value);
+                    return;
+                }
             }
+        }
+    }
 
-            // The Extension is the child of this Internal (common shape)
-            Node::Internal { children, .. } => {
-                let idx = stem[30] as usize;
-                match children[idx].as_deref_mut() {
-                    Some(Node::Extension {
-                        stem: node_stem,
-                        slots,
-                        slot_commitment: _,
-                    }) if *node_stem == stem => {
-                        slots[suf as usize] = Some(value);
-                        return;
-                    }
-                    None => {
-                        // create a fresh Extension for this stem
-                        let mut slots_arr = std::array::from_fn(|_| None);
-                        slots_arr[suf as usize] = Some(value);
-                        children[idx] = Some(Box::new(Node::Extension {
-                            stem,
-                            slots: slots_arr,
-                            slot_commitment: std::array::from_fn(|_| ZERO_VALUE::<V>()),
-                        }));
-                        return;
-                    }
-                    _ => unreachable!("invalid shape at depth 31"),
+    match node {
+        // Hit the stem bucket exactly here
+        Node::Extension {
+            stem: node_stem,
+            slots,
+            slot_commitment: _,
+        } if *node_stem == stem => {
+            slots[suf as usize] = Some(value);
+        }
+
+        // The Extension is the child of this Internal (common shape)
+        Node::Internal { children, .. } => {
+            let idx = stem_digit::<W>(&stem, stem_digits::<W>() - 1);
+            match children[idx].as_mut().map(Arc::make_mut) {
+                Some(Node::Extension {
+                    stem: node_stem,
+                    slots,
+                    slot_commitment: _,
+                }) if *node_stem == stem => {
+                    slots[suf as usize] = Some(value);
+                }
+                None => {
+                    // create a fresh Extension for this stem
+                    let mut slots_arr = std::array::from_fn(|_| None);
+                    slots_arr[suf as usize] = Some(value);
+                    children[idx] = Some(Arc::new(Node::Extension {
+                        stem,
+                        slots: slots_arr,
+                        slot_commitment: empty_digests::<V, W>(),
+                    }));
                 }
+                _ => unreachable!("invalid shape at full stem depth"),
             }
-
-            // Any other shape would be a construction bug
-            _ => unreachable!("unexpected node at depth 31"),
         }
+
+        // Any other shape would be a construction bug
+        _ => unreachable!("unexpected node at full stem depth"),
     }
+}
 
-    pub fn commit(&mut self) -> V::Commitment {
-        match self.root {
-            Some(ref mut n) => compute_commitment(&self.vc, n),
-            None => V::Commitment::default(),
+// Removes the stored value at (stem, suf) from the subtree `node`, which sits `depth` digits below
+// the root. Leaves an empty Extension in place of `node` if it held the last slot of the stem.
+fn remove_at<V: VectorCommitment<W>, const W: usize>(node: &mut Node<V, W>, depth: usize, stem: &Stem, suf: u8) -> Value {
+    match node {
+        Node::Extension { slots, .. } => slots[suf as usize].take().expect("key is stored"),
+        Node::Internal { children, .. } => {
+            let idx = stem_digit::<W>(stem, depth);
+            let child = children[idx].as_mut().expect("key is stored");
+            let old = remove_at(Arc::make_mut(child), depth + 1, stem, suf);
+            if is_empty_extension(child) {
+                children[idx] = None;
+            }
+
+            // A prefix left with a single stem is stored as that stem's Extension
+            let occupied: Vec<usize> = (0..W).filter(|&i| children[i].is_some()).collect();
+            if let [i] = occupied[..] {
+                if matches!(children[i].as_deref(), Some(Node::Extension { .. })) {
+                    *node = Arc::unwrap_or_clone(children[i].take().expect("occupied"));
+                }
+            }
+            old
         }
     }
+}
 
-    pub fn prove_get(&self, key: [u8; 32]) -> Option<VerkleProof<V>> {
-        let (stem, suf) = split_key(key);
-
-        let mut node = match self.root {
-            Some(ref n) => n,
-            None => return None,
-        };
+fn is_empty_extension<V: VectorCommitment<W>, const W: usize>(node: &Node<V, W>) -> bool {
+    matches!(node, Node::Extension { slots, .. } if slots.iter().all(Option::is_none))
+}
 
-        let mut proof_vec: VerkleProof<V> = VerkleProof { steps: Vec::new(), value: Vec::new() };
+/// Iterator over the entries of a [`VerkleTree`], see [`VerkleTree::iter`].
+pub struct Iter<'a, V: VectorCommitment<W>, const W: usize> {
+    stack: Vec<&'a Node<V, W>>,                          // nodes still to visit, next one on top
+    ext: Option<(&'a Stem, &'a [Option<Value>; W])>, // Extension being walked
+    suffix: usize,                                       // next slot of `ext` to look at
+}
 
-        for (_, &byte) in stem.iter().enumerate() {
-            let index = byte as usize;
-            match node {
-                Node::Internal { children, commitments} => {
-                    
-                    let node_commit = self.vc.commit_from_children(commitments);
-                    let (child_digest, proof) = self.vc.open_at(&commitments, index);
+impl<'a, V: VectorCommitment<W>, const W: usize> Iter<'a, V, W> {
+    // Entries of the subtree under `node`, in key order.
+    pub(crate) fn new(node: Option<&'a Node<V, W>>) -> Self {
+        Iter { stack: node.into_iter().collect(), ext: None, suffix: 0 }
+    }
+}
 
-                    assert_eq!(child_digest, commitments[index], "opening did not return correct value");
+impl<'a, V: VectorCommitment<W>, const W: usize> Iterator for Iter<'a, V, W> {
+    type Item = ([u8; 32], &'a Value);
 
-                    // Push a new proof
-                    proof_vec.steps.push(
-                        Step::Internal {
-                            parent_commit: node_commit,
-                            index: index,
-                            child_digest,
-                            proof,
-                        }
-                    );
-
-                    // Iterate to next node
-                    node = match children[index].as_deref() {
-                        Some(n) => n,
-                        None => return None,
-                    };
-                }
-                Node::Extension { stem: node_stem, slots, slot_commitment } => {
-                    if *node_stem != stem {
-                        return None;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((stem, slots)) = self.ext {
+                while self.suffix < W {
+                    let suf = self.suffix;
+                    self.suffix += 1;
+                    if let Some(value) = &slots[suf] {
+                        return Some((join_key(stem, suf as u8), value));
                     }
-                    let ext_commit = self.vc.commit_from_children(slot_commitment);
-                    // Open at the suffix (suf), not the current stem byte (index)
-                    let (slot_digest, proof) = self.vc.open_at(slot_commitment, suf as usize);
-                    proof_vec.steps.push(
-                        Step::Extension { ext_commit, index: suf as usize, proof }
-                    );
-                    proof_vec.value = slots[suf as usize].clone().unwrap().0;
-                    // Recompute expected digest binding stem+suffix+value
-                    let expected = digest_slot::<V>(node_stem, suf, &proof_vec.value);
-                    assert_eq!(slot_digest, expected, "slot digest mismatch (stem binding)");
-                    return Some(proof_vec);
                 }
+                self.ext = None;
             }
-        }
-
-        match node {
-            Node::Extension { stem: node_stem, slots, slot_commitment } if *node_stem == stem => {
-                let ext_commit = self.vc.commit_from_children(slot_commitment);
-                let (_, proof) = self.vc.open_at(&slot_commitment, suf as usize);
-                proof_vec.steps.push(
-                    Step::Extension { ext_commit, index: suf as usize, proof }
-                );
-                proof_vec.value = slots[suf as usize].clone().unwrap().0;
+            match self.stack.pop()? {
+                // Digits are taken most significant bits first, so child order is key order
+                Node::Internal { children, .. } => self.stack.extend(children.iter().rev().flatten().map(|c| &**c)),
+                Node::Extension { stem, slots, .. } => {
+                    self.ext = Some((stem, slots));
+                    self.suffix = 0;
+                }
             }
-            _ => unreachable!("unexpected node at depth 31"),
         }
-
-        Some(proof_vec)
     }
 }
@@ -1,44 +1,78 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use alloc::{format, string::String};
+
+use ark_ff::{FftField, PrimeField};
 use ark_poly::{univariate::DensePolynomial, DenseUVPolynomial, EvaluationDomain, Radix2EvaluationDomain as Domain};
-use ark_ff::PrimeField;
 use ark_serialize::CanonicalSerialize;
 
-use crate::vc::{VectorCommitment, ZERO32};
+use crate::hasher::{HashDomain, TreeHasher};
+#[cfg(feature = "std")]
+use crate::vc::ZERO32;
 
+#[cfg(feature = "std")]
 #[allow(non_snake_case)]
-pub(crate) fn ZERO_CHILD<V: VectorCommitment>() -> V::Fr {
-    hash_to_field::<V>(&ZERO32)
+pub(crate) fn ZERO_CHILD<F: PrimeField, H: TreeHasher>() -> F {
+    hash_in_domain::<F, H>(HashDomain::EmptyChild, &ZERO32)
 }
 
 #[allow(non_snake_case)]
-pub(crate) fn ZERO_VALUE<V: VectorCommitment>() -> V::Fr {
-    hash_to_field::<V>(&[])
+pub(crate) fn ZERO_VALUE<F: PrimeField, H: TreeHasher>() -> F {
+    hash_in_domain::<F, H>(HashDomain::EmptySlot, &[])
 }
 
-pub(crate) fn evals_to_poly<V: VectorCommitment>(domain: &Domain<V::Fr>, evals: &[V::Fr]) -> DensePolynomial<V::Fr> {
+pub(crate) fn evals_to_poly<F: FftField>(domain: &Domain<F>, evals: &[F]) -> DensePolynomial<F> {
     assert_eq!(domain.size(), evals.len());
     // IFFT: evaluations -> coefficients
     let coeffs = domain.ifft(evals);
     DensePolynomial::from_coefficients_vec(coeffs)
 }
 
-pub(crate) fn hash_to_field<V: VectorCommitment>(bytes: &[u8]) -> V::Fr {
-    let binding = blake3::hash(bytes);
-    let h = binding.as_bytes();  // 32 bytes
-    V::Fr::from_le_bytes_mod_order(h)
+// Hashes `bytes` framed for `domain`; scheme version 0 hashes them unframed.
+pub(crate) fn hash_in_domain<F: PrimeField, H: TreeHasher>(domain: HashDomain, bytes: &[u8]) -> F {
+    let prefix = H::domain_prefix(domain);
+    if prefix.is_empty() {
+        return H::hash_to_field::<F>(bytes);
+    }
+    let mut framed = Vec::with_capacity(prefix.len() + bytes.len());
+    framed.extend_from_slice(prefix);
+    framed.extend_from_slice(bytes);
+    H::hash_to_field::<F>(&framed)
 }
 
-pub(crate) fn digest_commit<V: VectorCommitment>(commit: &V::Commitment) -> V::Fr {
+/// Digest of a child commitment, as opened in its parent Internal node.
+pub fn digest_commit<F: PrimeField, H: TreeHasher>(commit: &impl CanonicalSerialize) -> F {
     let mut bytes = Vec::new();
     commit.serialize_compressed(&mut bytes).expect("serialize commitment");
-    hash_to_field::<V>(&bytes)
+    hash_in_domain::<F, H>(HashDomain::InternalChild, &bytes)
 }
 
-// Digest helper binding full stem + suffix + value bytes for an Extension slot.
-pub(crate) fn digest_slot<V: VectorCommitment>(stem: &[u8;31], suffix: u8, value: &[u8]) -> V::Fr {
-    let mut bytes = Vec::with_capacity(31 + 1 + value.len());
-    bytes.extend_from_slice(stem);
-    bytes.push(suffix);
+/// Digest of a stored value, binding its full key (stem and suffix) and the value bytes, as
+/// opened in its Extension node.
+pub fn digest_slot<F: PrimeField, H: TreeHasher>(key: &[u8; 32], value: &[u8]) -> F {
+    let mut bytes = Vec::with_capacity(32 + value.len());
+    bytes.extend_from_slice(key);
     bytes.extend_from_slice(value);
-    hash_to_field::<V>(&bytes)
+    hash_in_domain::<F, H>(HashDomain::ExtensionSlot, &bytes)
+}
+
+// Lowercase hex with a 0x prefix.
+#[cfg(feature = "std")]
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + 2 * bytes.len());
+    out.push_str("0x");
+    for b in bytes {
+        out.push_str(&format!("{b:02x}"));
+    }
+    out
 }
 
+// Even-length hex, with or without a 0x prefix.
+#[cfg(feature = "std")]
+pub(crate) fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    if s.len() % 2 == 1 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
@@ -1,26 +1,35 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use core::marker::PhantomData;
+
 use ark_ff::PrimeField;
 use ark_serialize::CanonicalSerialize;
+use bytes::Bytes;
 
-use crate::{node::{split_key, Node}, utils::{digest_commit, digest_slot, ZERO_CHILD, ZERO_VALUE}};
+use crate::{hasher::{Blake3Hasher, TreeHasher}, node::{join_key, split_key, stem_digit, stem_digits, Value}, utils::{digest_commit, digest_slot, ZERO_VALUE}};
+#[cfg(feature = "std")]
+use crate::{node::Node, utils::ZERO_CHILD};
 
+/// Default tree width, one stem byte per level.
 pub const ARITY: usize = 256;
 pub const ZERO32: [u8; 32] = [0; 32];
 
-/// VC interface
-pub trait VectorCommitment {
+/// VC interface over vectors of width W
+pub trait VectorCommitment<const W: usize = ARITY> {
     type Fr: PrimeField;
-    type Commitment: Default + PartialEq + Eq + Clone + std::fmt::Debug + CanonicalSerialize;
-    type Proof: Clone + std::fmt::Debug + PartialEq + Eq;
+    type Commitment: Default + PartialEq + Eq + Clone + core::fmt::Debug + CanonicalSerialize;
+    type Proof: Clone + core::fmt::Debug + PartialEq + Eq;
 
     // Typically constructed with an SRS and fixed domain elsewhere.
     // fn new(params: ...) -> Self where Self: Sized;
 
-    fn commit_from_children(&self, children: &[Self::Fr; ARITY]) -> Self::Commitment;
+    fn commit_from_children(&self, children: &[Self::Fr; W]) -> Self::Commitment;
 
     // Return both the field value and the proof (handy for the caller).
     fn open_at(
         &self,
-        children: &[Self::Fr; ARITY],
+        children: &[Self::Fr; W],
         index: usize,
     ) -> (Self::Fr, Self::Proof);
 
@@ -31,19 +40,45 @@ pub trait VectorCommitment {
         value_digest: Self::Fr,
         proof: &Self::Proof,
     ) -> bool;
+
+    // Recompute a commitment after changing some entries, knowing only their openings.
+    // Each update is (index, old digest, new digest, opening of old digest at index); all
+    // openings are against `commitment`. Returns None if any opening does not verify.
+    //
+    // Schemes that cannot update from openings alone keep this default, which always returns
+    // None; PartialTree then reports every write as rejected.
+    #[allow(clippy::type_complexity)]
+    fn update_at(
+        &self,
+        _commitment: &Self::Commitment,
+        _updates: &[(usize, Self::Fr, Self::Fr, &Self::Proof)],
+    ) -> Option<Self::Commitment> {
+        None
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerkleProof<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    pub steps: Vec<Step<V, W>>, // Internal hops (0..=some depth) + the final Extension hop
+    pub value: Bytes,         // claimed value (for inclusion)
+    pub(crate) hasher: PhantomData<H>, // digests were taken with H
 }
 
+/// Openings of several slots of one Extension under a single shared path, see
+/// [`VerkleTree::prove_stem`](crate::VerkleTree::prove_stem).
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct VerkleProof<V: VectorCommitment> {
-    pub steps: Vec<Step<V>>, // Internal hops (0..=some depth) + the final Extension hop
-    pub value: Vec<u8>,       // claimed value (for inclusion)
+pub struct StemProof<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY> {
+    pub steps: Vec<Step<V, W>>, // Internal hops only, down to the Extension
+    pub ext_commit: V::Commitment,
+    pub slots: Vec<(u8, Option<Bytes>, V::Proof)>, // (suffix, claimed value or None if empty, opening), ascending
+    pub(crate) hasher: PhantomData<H>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Step<V: VectorCommitment> {
+pub enum Step<V: VectorCommitment<W>, const W: usize = ARITY> {
     Internal {
         parent_commit: V::Commitment,
-        index: usize, // stem byte at this depth
+        index: usize, // stem digit at this depth
         child_digest: V::Fr, // Digest of child value at index
         proof: V::Proof, // opening(parent, index, child_digest)
     },
@@ -54,18 +89,23 @@ pub enum Step<V: VectorCommitment> {
     },
 }
 
-fn compute_internal_commitment<V: VectorCommitment>(vc: &V, node: &mut Node<V>) -> V::Commitment {
+#[cfg(feature = "std")]
+fn compute_internal_commitment<V: VectorCommitment<W>, H: TreeHasher, const W: usize>(vc: &V, node: &mut Node<V, W>) -> V::Commitment {
     match node {
         Node::Internal { children, commitments } => {
-            let mut child_digests: [V::Fr; ARITY] = std::array::from_fn(|_| ZERO_CHILD::<V>());
+            let mut child_digests: [V::Fr; W] = core::array::from_fn(|_| ZERO_CHILD::<V::Fr, H>());
             for (i, child_opt) in children.iter_mut().enumerate() {
-                if let Some(child) = child_opt.as_deref_mut() {
-                    // recurse to ensure children's commitments arrays are also populated
-                    let child_commit = compute_commitment::<V>(vc, child);
-                    let digest = digest_commit::<V>(&child_commit);
+                if let Some(child) = child_opt.as_mut() {
+                    let child_commit = match Arc::get_mut(child) {
+                        // recurse to ensure children's commitments arrays are also populated
+                        Some(child) => compute_commitment::<V, H, W>(vc, child),
+                        // Shared with a recorded version, so unchanged since it was committed
+                        None => cached_commitment(vc, child),
+                    };
+                    let digest = digest_commit::<V::Fr, H>(&child_commit);
                     child_digests[i] = digest;
                 } else {
-                    child_digests[i] = ZERO_CHILD::<V>();
+                    child_digests[i] = ZERO_CHILD::<V::Fr, H>();
                 }
             }
             let commit = vc.commit_from_children(&child_digests);
@@ -76,16 +116,17 @@ fn compute_internal_commitment<V: VectorCommitment>(vc: &V, node: &mut Node<V>)
     }
 }
 
-fn compute_extension_commitment<V: VectorCommitment>(vc: &V, node: &mut Node<V>) -> V::Commitment {
+#[cfg(feature = "std")]
+fn compute_extension_commitment<V: VectorCommitment<W>, H: TreeHasher, const W: usize>(vc: &V, node: &mut Node<V, W>) -> V::Commitment {
     match node {
         Node::Extension { stem, slots, slot_commitment, .. } => {
-            let mut value_digests: [V::Fr; ARITY] = std::array::from_fn(|_| ZERO_VALUE::<V>());
+            let mut value_digests: [V::Fr; W] = core::array::from_fn(|_| ZERO_VALUE::<V::Fr, H>());
             for (i, slot_opt) in slots.iter().enumerate() {
                 if let Some(value) = slot_opt {
-                    let digest = digest_slot::<V>(stem, i as u8, &value.0);
+                    let digest = digest_slot::<V::Fr, H>(&join_key(stem, i as u8), &value.0);
                     value_digests[i] = digest;
                 } else {
-                    value_digests[i] = ZERO_VALUE::<V>();
+                    value_digests[i] = ZERO_VALUE::<V::Fr, H>();
                 }
             }
             let commit = vc.commit_from_children(&value_digests);
@@ -96,22 +137,33 @@ fn compute_extension_commitment<V: VectorCommitment>(vc: &V, node: &mut Node<V>)
     }
 }
 
-pub(crate) fn compute_commitment<V: VectorCommitment>(vc: &V, node: &mut Node<V>) -> V::Commitment {
+#[cfg(feature = "std")]
+// Commitment of a node from the digests cached by its last commit.
+pub(crate) fn cached_commitment<V: VectorCommitment<W>, const W: usize>(vc: &V, node: &Node<V, W>) -> V::Commitment {
+    match node {
+        Node::Internal { commitments, .. } => vc.commit_from_children(commitments),
+        Node::Extension { slot_commitment, .. } => vc.commit_from_children(slot_commitment),
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn compute_commitment<V: VectorCommitment<W>, H: TreeHasher, const W: usize>(vc: &V, node: &mut Node<V, W>) -> V::Commitment {
     match node {
-        Node::Internal { .. } => compute_internal_commitment(vc, node),
-        Node::Extension { .. } => compute_extension_commitment(vc, node),
+        Node::Internal { .. } => compute_internal_commitment::<V, H, W>(vc, node),
+        Node::Extension { .. } => compute_extension_commitment::<V, H, W>(vc, node),
     }
 }
 
-pub fn verify_proof<V: VectorCommitment>(vc: &V, root_commit: &V::Commitment, proof: &VerkleProof<V>, key: [u8; 32]) -> bool {
-    let (stem, suf) = split_key(key);
+pub fn verify_proof<V: VectorCommitment<W>, H: TreeHasher, const W: usize>(vc: &V, root_commit: &V::Commitment, proof: &VerkleProof<V, H, W>, key: [u8; 32]) -> bool {
+    let (stem, suf) = split_key::<W>(key);
     let value = &proof.value;
+    let depth = stem_digits::<W>();
 
     if proof.steps.is_empty() {
         return false;
     }
 
-    if proof.steps.len() > stem.len() + 1 { // at most all stem bytes + final extension
+    if proof.steps.len() > depth + 1 { // at most all stem digits + final extension
         return false; // Too many steps
     }
 
@@ -131,7 +183,7 @@ pub fn verify_proof<V: VectorCommitment>(vc: &V, root_commit: &V::Commitment, pr
         if i == 0 {
             if commit_ref != root_commit { return false; }
         } else {
-            let got = digest_commit::<V>(commit_ref);
+            let got = digest_commit::<V::Fr, H>(commit_ref);
             if Some(got) != expected_digest { return false; }
         }
 
@@ -139,10 +191,10 @@ pub fn verify_proof<V: VectorCommitment>(vc: &V, root_commit: &V::Commitment, pr
             Step::Internal { parent_commit, index, child_digest, proof: opening_proof } => {
                 // Verify opening
                 if !vc.verify_at(parent_commit, *index, *child_digest, opening_proof) { return false; }
+                // Internal node cannot be after consuming all stem digits
+                if i == depth { return false; }
                 // Path index correctness
-                if *index != stem[i] as usize { return false; }
-                // Internal node cannot be after consuming all stem bytes
-                if i == stem.len() { return false; }
+                if *index != stem_digit::<W>(&stem, i) { return false; }
                 // Next commitment (child) must hash to this child_digest
                 expected_digest = Some(*child_digest);
                 // An Internal step cannot be the last step (must end with Extension)
@@ -152,7 +204,7 @@ pub fn verify_proof<V: VectorCommitment>(vc: &V, root_commit: &V::Commitment, pr
                 // Suffix index correctness
                 if *index != suf as usize { return false; }
                 // Verify the slot opening to the value digest
-                let val_digest = digest_slot::<V>(&stem, suf, &value);
+                let val_digest = digest_slot::<V::Fr, H>(&key, value);
                 if !vc.verify_at(ext_commit, *index, val_digest, opening_proof) { return false; }
                 // Extension must be terminal
                 if i + 1 != proof.steps.len() { return false; }
@@ -166,4 +218,47 @@ pub fn verify_proof<V: VectorCommitment>(vc: &V, root_commit: &V::Commitment, pr
     true
 }
 
+/// Verifies a [`StemProof`] for `stem` and returns the proven slots, `None` marking a slot that
+/// is proven empty. Returns None if the proof does not verify. `stem` may be any key of the
+/// stem; its suffix bits are ignored.
+pub fn verify_stem_proof<V: VectorCommitment<W>, H: TreeHasher, const W: usize>(vc: &V, root_commit: &V::Commitment, proof: &StemProof<V, H, W>, stem: [u8; 32]) -> Option<BTreeMap<u8, Option<Value>>> {
+    let (stem, _) = split_key::<W>(stem);
+    if proof.steps.len() > stem_digits::<W>() {
+        return None; // Too many steps
+    }
+
+    // Each commitment must be the root or hash to the digest opened one level up
+    let mut expected_digest: Option<V::Fr> = None;
+    let linked = |commit: &V::Commitment, expected: Option<V::Fr>| match expected {
+        None => commit == root_commit,
+        Some(digest) => digest_commit::<V::Fr, H>(commit) == digest,
+    };
+
+    for (level, step) in proof.steps.iter().enumerate() {
+        let Step::Internal { parent_commit, index, child_digest, proof: opening_proof } = step else {
+            return None; // The Extension is carried separately
+        };
+        if !linked(parent_commit, expected_digest) { return None; }
+        if *index != stem_digit::<W>(&stem, level) { return None; }
+        if !vc.verify_at(parent_commit, *index, *child_digest, opening_proof) { return None; }
+        expected_digest = Some(*child_digest);
+    }
+    if !linked(&proof.ext_commit, expected_digest) { return None; }
+
+    let mut proven = BTreeMap::new();
+    for (suf, value, opening_proof) in &proof.slots {
+        // Suffixes are strictly ascending, so none is claimed twice
+        if *suf as usize >= W || proven.last_key_value().is_some_and(|(last, _)| last >= suf) {
+            return None;
+        }
+        let digest = match value {
+            Some(v) => digest_slot::<V::Fr, H>(&join_key(&stem, *suf), v),
+            None => ZERO_VALUE::<V::Fr, H>(),
+        };
+        if !vc.verify_at(&proof.ext_commit, *suf as usize, digest, opening_proof) { return None; }
+        proven.insert(*suf, value.clone().map(Value));
+    }
+    Some(proven)
+}
+
 // (former check_parent_child_commits logic now inlined in verify_proof with per-hop chaining)
@@ -0,0 +1,66 @@
+use crate::{
+    hasher::TreeHasher,
+    node::{split_key, Node},
+    tree::{lookup, VerkleTree},
+    vc::{VectorCommitment, VerkleProof},
+    Value,
+};
+
+/// A root recorded by [`VerkleTree::commit_version`].
+///
+/// Holds a copy of the root node only. Its children are shared with the live tree and with the
+/// other versions until an insert copies the nodes on its path, so a version costs about the
+/// nodes written between it and the next one.
+pub(crate) struct Version<V: VectorCommitment<W>, const W: usize> {
+    commit: V::Commitment,
+    root: Option<Node<V, W>>,
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> VerkleTree<V, H, W> {
+    /// Commits the tree and records the result as version `version`, which must be greater than
+    /// every version recorded so far.
+    pub fn commit_version(&mut self, version: u64) -> V::Commitment {
+        if let Some((&last, _)) = self.versions.last_key_value() {
+            assert!(version > last, "version {version} is not after the latest version {last}");
+        }
+        let commit = self.commit();
+        self.versions.insert(version, Version { commit: commit.clone(), root: self.root.clone() });
+        commit
+    }
+
+    /// The root recorded as `version`, if it is still retained.
+    pub fn root_at(&self, version: u64) -> Option<V::Commitment> {
+        self.versions.get(&version).map(|v| v.commit.clone())
+    }
+
+    /// The versions still retained, in ascending order.
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.versions.keys().copied()
+    }
+
+    /// The value at `key` as of `version`. Returns None if the key was not set then, or the
+    /// version is not retained.
+    pub fn get_at(&self, version: u64, key: [u8; 32]) -> Option<Value> {
+        let (stem, suf) = split_key::<W>(key);
+        lookup(self.versions.get(&version)?.root.as_ref(), 0, &stem, suf)
+    }
+
+    /// Proves the value at `key` as of `version`, against [`VerkleTree::root_at`]. Returns None
+    /// if the key was not set then, or the version is not retained.
+    pub fn prove_get_at(&self, version: u64, key: [u8; 32]) -> Option<VerkleProof<V, H, W>> {
+        self.prove_get_in(self.versions.get(&version)?.root.as_ref(), key)
+    }
+
+    /// Drops the versions that fall outside the last `window` version numbers, counting back
+    /// from the latest version, and returns how many were dropped. Nodes no longer shared with
+    /// a retained version or the live tree are freed.
+    pub fn prune_versions(&mut self, window: u64) -> usize {
+        let Some((&latest, _)) = self.versions.last_key_value() else {
+            return 0;
+        };
+        let oldest = (latest + 1).saturating_sub(window);
+        let before = self.versions.len();
+        self.versions = self.versions.split_off(&oldest);
+        before - self.versions.len()
+    }
+}
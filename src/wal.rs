@@ -0,0 +1,265 @@
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use ark_serialize::CanonicalSerialize;
+
+use crate::{
+    hasher::{Blake3Hasher, TreeHasher},
+    tree::VerkleTree,
+    vc::{VectorCommitment, ARITY},
+    Value,
+};
+
+const INSERT: u8 = 1;
+const REMOVE: u8 = 2;
+const COMMIT: u8 = 3;
+const CHECKSUM: usize = 4;
+
+#[derive(Debug)]
+pub enum WalError {
+    Io(io::Error),
+    /// The commit marker ending at byte `offset` records a different root than the replayed
+    /// tree, e.g. because the log was written with another vector commitment setup.
+    RootMismatch { offset: usize },
+    /// The record at byte `offset` is damaged, yet a valid record follows it, so it is not a
+    /// write cut short by a crash. Replaying past it could lose committed batches, so the log is
+    /// left untouched.
+    Corrupt { offset: usize },
+}
+
+impl fmt::Display for WalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalError::Io(e) => write!(f, "log access failed: {e}"),
+            WalError::RootMismatch { offset } => write!(f, "replayed tree does not match the root committed at byte {offset}"),
+            WalError::Corrupt { offset } => write!(f, "log record at byte {offset} is damaged but followed by valid records"),
+        }
+    }
+}
+
+impl std::error::Error for WalError {}
+
+impl From<io::Error> for WalError {
+    fn from(e: io::Error) -> Self {
+        WalError::Io(e)
+    }
+}
+
+enum Record {
+    Insert([u8; 32], Value),
+    Remove([u8; 32]),
+    Commit(Vec<u8>),
+}
+
+// Appends `record` to `out`, followed by its checksum.
+fn encode_record(record: &Record, out: &mut Vec<u8>) {
+    let start = out.len();
+    match record {
+        Record::Insert(key, value) => {
+            out.push(INSERT);
+            out.extend_from_slice(key);
+            out.extend_from_slice(&(value.0.len() as u32).to_le_bytes());
+            out.extend_from_slice(&value.0);
+        }
+        Record::Remove(key) => {
+            out.push(REMOVE);
+            out.extend_from_slice(key);
+        }
+        Record::Commit(root) => {
+            out.push(COMMIT);
+            out.extend_from_slice(&(root.len() as u32).to_le_bytes());
+            out.extend_from_slice(root);
+        }
+    }
+    let checksum = blake3::hash(&out[start..]);
+    out.extend_from_slice(&checksum.as_bytes()[..CHECKSUM]);
+}
+
+// The record at the start of `bytes` and its encoded length. None if the record is cut short or
+// damaged, i.e. it was being written when the process stopped.
+fn decode_record(bytes: &[u8]) -> Option<(Record, usize)> {
+    let take = |at: usize, len: usize| bytes.get(at..at + len);
+    let length = |at: usize| take(at, 4).map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes")) as usize);
+
+    let (record, len) = match *bytes.first()? {
+        INSERT => {
+            let key = take(1, 32)?.try_into().expect("32 bytes");
+            let value = take(37, length(33)?)?;
+            (Record::Insert(key, Value::from(value.to_vec())), 37 + value.len())
+        }
+        REMOVE => (Record::Remove(take(1, 32)?.try_into().expect("32 bytes")), 33),
+        COMMIT => {
+            let root = take(5, length(1)?)?;
+            (Record::Commit(root.to_vec()), 5 + root.len())
+        }
+        _ => return None,
+    };
+    let checksum = take(len, CHECKSUM)?;
+    (checksum == &blake3::hash(&bytes[..len]).as_bytes()[..CHECKSUM]).then_some((record, len + CHECKSUM))
+}
+
+fn root_bytes(root: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    root.serialize_compressed(&mut bytes).expect("serialize commitment");
+    bytes
+}
+
+/// Storage for the log of a [`WalTree`]. Writes always go to the end, like a file opened for
+/// appending.
+pub trait WalLog: Read + Write {
+    /// Cuts the log to its first `len` bytes.
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+
+    /// Makes everything written so far durable.
+    fn sync_data(&mut self) -> io::Result<()>;
+}
+
+impl WalLog for File {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+/// A [`VerkleTree`] persisted as a write-ahead log of its inserts and removes.
+///
+/// Every write is appended to the log before it is applied, and `commit` appends a marker with
+/// the root and syncs the file. The writes since the last marker form a batch. `open` replays
+/// the complete batches and truncates the log after the last marker, so after a crash the tree
+/// comes back at the last committed root and a partial batch is discarded. A damaged record is
+/// only taken for a crash if no valid record follows it; otherwise `open` fails with
+/// [`WalError::Corrupt`] and leaves the log as it is.
+///
+/// Each record is its tag and payload followed by the first 4 bytes of their BLAKE3 hash:
+/// - insert: `0x01 || key || value length (u32 LE) || value`
+/// - remove: `0x02 || key`
+/// - commit: `0x03 || root length (u32 LE) || compressed root`
+///
+/// A record that fails to be written is cut off again, so the log never holds a torn record
+/// before later ones. If even that fails, the `WalTree` refuses further writes.
+pub struct WalTree<V: VectorCommitment<W>, H: TreeHasher = Blake3Hasher, const W: usize = ARITY, L: WalLog = File> {
+    tree: VerkleTree<V, H, W>,
+    log: L,
+    len: u64,       // bytes in the log, all of them complete records
+    poisoned: bool, // a torn record could not be cut off
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize> WalTree<V, H, W> {
+    /// Opens the log at `path`, creating it if missing, and recovers the last committed tree.
+    pub fn open(vc: V, path: impl AsRef<Path>) -> Result<Self, WalError> {
+        let log = OpenOptions::new().read(true).append(true).create(true).open(path)?;
+        Self::with_log(vc, log)
+    }
+}
+
+impl<V: VectorCommitment<W>, H: TreeHasher, const W: usize, L: WalLog> WalTree<V, H, W, L> {
+    /// Recovers the last committed tree from `log` and appends further writes to it.
+    pub fn with_log(vc: V, mut log: L) -> Result<Self, WalError> {
+        let mut bytes = Vec::new();
+        log.read_to_end(&mut bytes)?;
+
+        let (tree, committed) = Self::recover(vc, &bytes)?;
+        if committed < bytes.len() {
+            // Appends go to the end of the log, so drop the partial batch before writing again
+            log.set_len(committed as u64)?;
+            log.sync_data()?;
+        }
+        Ok(WalTree { tree, log, len: committed as u64, poisoned: false })
+    }
+
+    /// Replays the complete batches of `log`. Returns the tree at the last commit marker and the
+    /// length of the log up to and including that marker. Fails with [`WalError::Corrupt`] if a
+    /// damaged record is followed by a valid one.
+    pub fn recover(vc: V, log: &[u8]) -> Result<(VerkleTree<V, H, W>, usize), WalError> {
+        let mut tree = VerkleTree::new(vc);
+        let mut batch = Vec::new();
+        let (mut offset, mut committed, mut root) = (0, 0, None);
+
+        while let Some((record, len)) = decode_record(&log[offset..]) {
+            offset += len;
+            match record {
+                Record::Commit(r) => {
+                    for record in batch.drain(..) {
+                        match record {
+                            Record::Insert(key, value) => tree.insert(key, value),
+                            Record::Remove(key) => {
+                                tree.remove(key);
+                            }
+                            Record::Commit(_) => unreachable!("batches end at a commit"),
+                        }
+                    }
+                    committed = offset;
+                    root = Some(r);
+                }
+                write => batch.push(write),
+            }
+        }
+        // A crash only tears the last record, so anything valid after it means the log is damaged
+        if (offset + 1..log.len()).any(|at| decode_record(&log[at..]).is_some()) {
+            return Err(WalError::Corrupt { offset });
+        }
+
+        // Roots of earlier markers are not checked, as the last one covers every batch
+        if let Some(root) = root {
+            if root_bytes(&tree.commit()) != root {
+                return Err(WalError::RootMismatch { offset: committed });
+            }
+        }
+        Ok((tree, committed))
+    }
+
+    fn append(&mut self, record: &Record) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other("log holds a torn record that could not be cut off"));
+        }
+        let mut bytes = Vec::new();
+        encode_record(record, &mut bytes);
+        if let Err(e) = self.log.write_all(&bytes) {
+            // Part of the record may have been written, and replay would stop there
+            self.poisoned = self.log.set_len(self.len).is_err();
+            return Err(e);
+        }
+        self.len += bytes.len() as u64;
+        Ok(())
+    }
+
+    pub fn get(&self, key: [u8; 32]) -> Option<Value> {
+        self.tree.get(key)
+    }
+
+    /// Logs the insert, then applies it. It survives a crash once the next `commit` returns.
+    pub fn insert(&mut self, key: [u8; 32], value: Value) -> io::Result<()> {
+        self.append(&Record::Insert(key, value.clone()))?;
+        self.tree.insert(key, value);
+        Ok(())
+    }
+
+    /// Logs the remove, then applies it. Removing an absent key is not logged.
+    pub fn remove(&mut self, key: [u8; 32]) -> io::Result<Option<Value>> {
+        if self.tree.get(key).is_none() {
+            return Ok(None);
+        }
+        self.append(&Record::Remove(key))?;
+        Ok(self.tree.remove(key))
+    }
+
+    /// Commits the tree, then logs the root and syncs the log, which makes the batch durable.
+    pub fn commit(&mut self) -> io::Result<V::Commitment> {
+        let root = self.tree.commit();
+        self.append(&Record::Commit(root_bytes(&root)))?;
+        self.log.sync_data()?;
+        Ok(root)
+    }
+
+    /// The tree, including writes not committed yet.
+    pub fn tree(&self) -> &VerkleTree<V, H, W> {
+        &self.tree
+    }
+}
@@ -0,0 +1,86 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use verkle::{KzgVc, Value, VerkleTree};
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_diverging_at(d: usize, b: u8) -> [u8; 31] {
+    let mut s = [0x55u8; 31];
+    s[d] = b;
+    s
+}
+
+fn permutations(items: &[([u8; 32], Vec<u8>)]) -> Vec<Vec<([u8; 32], Vec<u8>)>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+    let mut out = Vec::new();
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+        for mut tail in permutations(&rest) {
+            tail.insert(0, first.clone());
+            out.push(tail);
+        }
+    }
+    out
+}
+
+#[test]
+fn every_insertion_order_gives_same_root() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    // Stems that split at the root, in the middle and at the last stem byte, one of them holding two slots.
+    let entries = vec![
+        (key_from_bytes(stem_diverging_at(0, 0x01), 0x00), b"a".to_vec()),
+        (key_from_bytes(stem_diverging_at(2, 0x02), 0x01), b"b".to_vec()),
+        (key_from_bytes(stem_diverging_at(30, 0x03), 0x02), b"c".to_vec()),
+        (key_from_bytes(stem_diverging_at(30, 0x03), 0xFF), b"d".to_vec()),
+    ];
+
+    let mut expected = None;
+    for order in permutations(&entries) {
+        let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+        for (k, v) in order {
+            t.insert(k, Value::from(v));
+            assert!(t.is_canonical());
+        }
+        let root = t.commit();
+        assert_eq!(*expected.get_or_insert(root), root);
+    }
+}
+
+#[test]
+fn shuffled_random_inserts_give_same_root() {
+    let mut rng = StdRng::seed_from_u64(0xBEEFCAFE1234);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let mut entries = Vec::new();
+    for _ in 0..64 {
+        // Only the first two stem bytes vary, so stems collide and split often.
+        let mut stem = [0u8; 31];
+        stem[0] = rng.gen_range(0..4);
+        stem[1] = rng.gen_range(0..4);
+        entries.push((key_from_bytes(stem, rng.gen()), vec![rng.gen()]));
+    }
+    // Keep one value per key so that every order stores the same key/value set.
+    entries.sort_by_key(|(k, _)| *k);
+    entries.dedup_by_key(|(k, _)| *k);
+
+    let mut roots = Vec::new();
+    for _ in 0..4 {
+        entries.shuffle(&mut rng);
+        let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+        for (k, v) in &entries {
+            t.insert(*k, Value::from(v.clone()));
+        }
+        assert!(t.is_canonical());
+        roots.push(t.commit());
+    }
+    assert!(roots.windows(2).all(|w| w[0] == w[1]));
+}
@@ -24,7 +24,7 @@ fn get_non_existent_key_returns_none() {
     // Insert one key
     let stem = stem_repeat(0xAB);
     let present = key_from_bytes(stem, 0x01);
-    t.insert(present, Value(b"exists".to_vec()));
+    t.insert(present, Value::from(b"exists".to_vec()));
 
     // Query the same stem, different suffix → should be None
     let absent_same_stem = key_from_bytes(stem, 0x02);
@@ -50,7 +50,7 @@ fn chaos_many_inserts_and_reads() {
     for suf in [0x00u8, 0x01, 0x02, 0x7F, 0x80, 0xFE, 0xFF] {
         let k = key_from_bytes(shared_stem_a, suf);
         let v = format!("A:{suf:02X}").into_bytes();
-        t.insert(k, Value(v.clone()));
+        t.insert(k, Value::from(v.clone()));
         expected.insert(k, v);
     }
 
@@ -59,23 +59,20 @@ fn chaos_many_inserts_and_reads() {
     for suf in 0..32u8 {
         let k = key_from_bytes(shared_stem_b, suf);
         let v = format!("B:{suf:02X}:v1").into_bytes();
-        t.insert(k, Value(v.clone()));
+        t.insert(k, Value::from(v.clone()));
         expected.insert(k, v);
     }
     // Overwrite some slots on the same stem
     for suf in [0x00u8, 0x10, 0x1F] {
         let k = key_from_bytes(shared_stem_b, suf);
         let v2 = format!("B:{suf:02X}:v2").into_bytes();
-        t.insert(k, Value(v2.clone()));
+        t.insert(k, Value::from(v2.clone()));
         expected.insert(k, v2);
     }
 
     // --- Bucket 3: Stems that share a LONG prefix but diverge at various depths (exercise splits) ---
     // Build a base prefix and then vary a byte at different positions.
-    let mut base = [0u8; 31];
-    for i in 0..31 {
-        base[i] = 0xAA;
-    } // common prefix
+    let base = [0xAAu8; 31]; // common prefix
     let divergence_points = [0usize, 5, 10, 15, 25, 30]; // include first and last byte divergences
     for &d in &divergence_points {
         let mut s1 = base;
@@ -87,12 +84,12 @@ fn chaos_many_inserts_and_reads() {
         for suf in [0x03u8, 0xF3] {
             let k1 = key_from_bytes(s1, suf);
             let v1 = format!("C:d{d}:s1:{suf:02X}").into_bytes();
-            t.insert(k1, Value(v1.clone()));
+            t.insert(k1, Value::from(v1.clone()));
             expected.insert(k1, v1);
 
             let k2 = key_from_bytes(s2, suf);
             let v2 = format!("C:d{d}:s2:{suf:02X}").into_bytes();
-            t.insert(k2, Value(v2.clone()));
+            t.insert(k2, Value::from(v2.clone()));
             expected.insert(k2, v2);
         }
     }
@@ -112,7 +109,7 @@ fn chaos_many_inserts_and_reads() {
             rng.fill(bytes.as_mut_slice());
             bytes
         };
-        t.insert(k, Value(v.clone()));
+        t.insert(k, Value::from(v.clone()));
         expected.insert(k, v);
     }
 
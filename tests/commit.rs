@@ -1,3 +1,4 @@
+use bytes::Bytes;
 use rand::{rngs::StdRng, SeedableRng};
 use verkle::{vc::verify_proof, KzgVc, Value, VerkleTree
 };
@@ -21,7 +22,7 @@ fn verify_single_key() {
     let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
     let key = key_from_bytes(stem_repeat(1), 2);
-    let value = Value(vec![3, 4, 5]);
+    let value = Value::from(vec![3, 4, 5]);
     tree.insert(key, value);
 
     let root = tree.commit();
@@ -36,13 +37,13 @@ fn fail_incorrect_proof() {
     let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
     let key = key_from_bytes(stem_repeat(1), 2);
-    let value = Value(vec![3, 4, 5]);
+    let value = Value::from(vec![3, 4, 5]);
     tree.insert(key, value);
 
     let root = tree.commit(); 
 
     let mut proof = tree.prove_get(key).unwrap();
-    proof.value[0] = 99; // Corrupt proof
+    proof.value = Bytes::from_static(&[99, 4, 5]); // Corrupt proof
     assert!(!verify_proof(&kzg, &root, &proof, key));
 }
 
@@ -52,11 +53,11 @@ fn verify_sibling_keys() {
     let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
     let key1 = key_from_bytes(stem_repeat(1), 2);
-    let value1 = Value(vec![3, 4, 5]);
+    let value1 = Value::from(vec![3, 4, 5]);
     tree.insert(key1, value1);
 
     let key2 = key_from_bytes(stem_repeat(1), 3);
-    let value2 = Value(vec![6, 7, 8]);
+    let value2 = Value::from(vec![6, 7, 8]);
     tree.insert(key2, value2);
 
     let root = tree.commit();
@@ -74,7 +75,7 @@ fn verify_early_extension() {
     let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
     let key1 = key_from_bytes(stem_repeat(1), 2);
-    let value1 = Value(vec![3, 4, 5]);
+    let value1 = Value::from(vec![3, 4, 5]);
     tree.insert(key1, value1);
 
     let root1 = tree.commit();
@@ -83,7 +84,7 @@ fn verify_early_extension() {
 
     let mut key2 = key_from_bytes(stem_repeat(1), 3); // Different stem diverges at byte 5
     key2[2] = 99;
-    let value2 = Value(vec![6, 7, 8]);
+    let value2 = Value::from(vec![6, 7, 8]);
     tree.insert(key2, value2);
 
     let root2 = tree.commit();
@@ -101,7 +102,7 @@ fn verify_last_byte_divergence() {
     let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
     let key1 = key_from_bytes(stem_repeat(1), 2);
-    let value1 = Value(vec![3, 4, 5]);
+    let value1 = Value::from(vec![3, 4, 5]);
     tree.insert(key1, value1);
 
     let root1 = tree.commit();
@@ -110,7 +111,7 @@ fn verify_last_byte_divergence() {
 
     let mut key2 = key_from_bytes(stem_repeat(1), 3); // Different stem diverges at byte 30
     key2[30] = 99;
-    let value2 = Value(vec![6, 7, 8]);
+    let value2 = Value::from(vec![6, 7, 8]);
     tree.insert(key2, value2);
 
     let root2 = tree.commit();
@@ -128,7 +129,7 @@ fn verify_incorrect_length_proof() {
     let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
     let key = key_from_bytes(stem_repeat(1), 2);
-    let value = Value(vec![3, 4, 5]);
+    let value = Value::from(vec![3, 4, 5]);
     tree.insert(key, value);
 
     let root = tree.commit();
@@ -0,0 +1,82 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{
+    compact::{verify_compact_proof, CompactProof},
+    vc::{verify_proof, Step},
+    KzgVc, Value, VerkleTree,
+};
+
+type Compact = CompactProof<KzgVc<'static>>;
+
+fn random_tree(kzg: &KzgVc<'static>, rng: &mut StdRng) -> VerkleTree<KzgVc<'static>> {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for _ in 0..30 {
+        let mut k: [u8; 32] = rng.gen();
+        // Shared prefixes, so proofs have several Internal steps
+        k[0] = rng.gen_range(0..3);
+        k[1] = rng.gen_range(0..2);
+        k[31] = rng.gen_range(0..4);
+        t.insert(k, Value::from(vec![rng.gen(); rng.gen_range(0..20)]));
+    }
+    t
+}
+
+#[test]
+fn compact_proofs_verify_and_are_smaller() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = random_tree(&kzg, &mut rng);
+    let root = tree.commit();
+
+    for (k, _) in tree.iter() {
+        let full = tree.prove_get(k).unwrap();
+        let compact = full.compact();
+        assert!(verify_compact_proof(&kzg, &root, &compact, k));
+
+        let bytes = compact.to_bytes();
+        let decoded = Compact::from_bytes(&bytes).expect("roundtrip");
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert!(verify_compact_proof(&kzg, &root, &decoded, k));
+        // The expanded proof is the original one
+        let expanded = decoded.expand(&root, k).unwrap();
+        assert!(verify_proof(&kzg, &root, &expanded, k));
+        assert_eq!(expanded.compact().to_bytes(), bytes);
+
+        // 48-byte commitments and openings, 32-byte digests and 8-byte indices per step
+        let internal_steps = full.steps.iter().filter(|s| matches!(s, Step::Internal { .. })).count();
+        let full_len = full.steps.len() * (48 + 48 + 8) + internal_steps * 32 + 4 + full.value.len();
+        assert!(bytes.len() + 48 <= full_len);
+    }
+}
+
+#[test]
+fn tampered_compact_proofs_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = random_tree(&kzg, &mut rng);
+    let root = tree.commit();
+
+    let (k, _) = tree.iter().find(|(k, _)| tree.prove_get(*k).unwrap().steps.len() > 2).unwrap();
+    let compact = tree.prove_get(k).unwrap().compact();
+
+    // Indices come from the key, so the proof does not transfer to a sibling key
+    let mut other = k;
+    other[30] ^= 1;
+    assert!(!verify_compact_proof(&kzg, &root, &compact, other));
+
+    let mut forged = compact.clone();
+    forged.value = bytes::Bytes::from_static(b"forged");
+    assert!(!verify_compact_proof(&kzg, &root, &forged, k));
+
+    let mut swapped = compact.clone();
+    swapped.commitments.swap(0, 1);
+    assert!(!verify_compact_proof(&kzg, &root, &swapped, k));
+
+    let mut extra = compact.clone();
+    extra.proofs.push(extra.proofs[0]);
+    assert!(extra.expand(&root, k).is_none());
+
+    let bytes = compact.to_bytes();
+    assert!(Compact::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    assert!(Compact::from_bytes(&[bytes.clone(), vec![0]].concat()).is_none());
+    assert!(Compact::from_bytes(&[]).is_none());
+}
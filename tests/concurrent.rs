@@ -0,0 +1,93 @@
+use std::thread;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{vc::verify_proof, ConcurrentVerkleTree, KzgVc, Value, VerkleTree};
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_repeat(b: u8) -> [u8; 31] {
+    let mut s = [0u8; 31];
+    s.fill(b);
+    s
+}
+
+fn sequential_root(kzg: &KzgVc<'static>, entries: &[([u8; 32], Vec<u8>)]) -> <KzgVc<'static> as verkle::vc::VectorCommitment>::Commitment {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for (k, v) in entries {
+        t.insert(*k, Value::from(v.clone()));
+    }
+    t.commit()
+}
+
+#[test]
+fn parallel_writers_give_the_sequential_root() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let entries: Vec<([u8; 32], Vec<u8>)> = (0..64)
+        .map(|_| {
+            let mut k: [u8; 32] = rng.gen();
+            // Several writers share a first byte, and so a shard
+            k[0] = rng.gen_range(0..6);
+            (k, vec![rng.gen(); 4])
+        })
+        .collect();
+
+    let tree = ConcurrentVerkleTree::<KzgVc>::new(kzg.clone());
+    thread::scope(|scope| {
+        for chunk in entries.chunks(8) {
+            let tree = &tree;
+            scope.spawn(move || {
+                for (k, v) in chunk {
+                    tree.insert(*k, Value::from(v.clone()));
+                }
+            });
+        }
+    });
+
+    let root = tree.commit();
+    assert_eq!(root, sequential_root(&kzg, &entries));
+    for (k, v) in &entries {
+        assert_eq!(tree.get(*k), Some(Value::from(v.clone())));
+    }
+
+    let mut merged = tree.into_tree();
+    assert!(merged.is_canonical());
+    assert_eq!(merged.commit(), root);
+    let (k, _) = entries[5];
+    assert!(verify_proof(&kzg, &root, &merged.prove_get(k).unwrap(), k));
+}
+
+#[test]
+fn small_trees_match_the_sequential_shape() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let mut same_shard = stem_repeat(0x11);
+    same_shard[30] = 0x12;
+    let cases: Vec<Vec<([u8; 32], Vec<u8>)>> = vec![
+        vec![],
+        // A single stem is a lone Extension at the root
+        vec![(key_from_bytes(stem_repeat(0x11), 0), vec![1]), (key_from_bytes(stem_repeat(0x11), 9), vec![2])],
+        // Two stems in one shard still need an Internal root
+        vec![(key_from_bytes(stem_repeat(0x11), 0), vec![1]), (key_from_bytes(same_shard, 0), vec![2])],
+        vec![(key_from_bytes(stem_repeat(0x11), 0), vec![1]), (key_from_bytes(stem_repeat(0x22), 0), vec![2])],
+    ];
+
+    for entries in cases {
+        let tree = ConcurrentVerkleTree::<KzgVc>::new(kzg.clone());
+        for (k, v) in &entries {
+            tree.insert(*k, Value::from(v.clone()));
+        }
+        let root = tree.commit();
+        assert_eq!(root, sequential_root(&kzg, &entries));
+        let mut merged = tree.into_tree();
+        assert!(merged.is_canonical());
+        assert_eq!(merged.commit(), root);
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{diff::{Change, DiffError}, KzgVc, Value, VerkleTree};
+
+fn random_key(rng: &mut StdRng) -> [u8; 32] {
+    let mut k: [u8; 32] = rng.gen();
+    // Few distinct prefixes, so stems share paths and Extensions get split and merged
+    k[0] = rng.gen_range(0..3);
+    k[1] = rng.gen_range(0..3);
+    k[31] = rng.gen_range(0..4);
+    k
+}
+
+fn build(kzg: &KzgVc<'static>, entries: &BTreeMap<[u8; 32], Vec<u8>>) -> VerkleTree<KzgVc<'static>> {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for (k, v) in entries {
+        t.insert(*k, Value::from(v.clone()));
+    }
+    t.commit();
+    t
+}
+
+fn expected_diff(old: &BTreeMap<[u8; 32], Vec<u8>>, new: &BTreeMap<[u8; 32], Vec<u8>>) -> Vec<Change> {
+    let keys: std::collections::BTreeSet<_> = old.keys().chain(new.keys()).copied().collect();
+    keys.into_iter()
+        .filter_map(|key| match (old.get(&key), new.get(&key)) {
+            (Some(a), Some(b)) if a != b => Some(Change::Modified { key, old: Value::from(a.clone()), new: Value::from(b.clone()) }),
+            (Some(a), None) => Some(Change::Removed { key, old: Value::from(a.clone()) }),
+            (None, Some(b)) => Some(Change::Added { key, new: Value::from(b.clone()) }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn diff_matches_model() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    for _ in 0..6 {
+        let mut old = BTreeMap::new();
+        for _ in 0..24 {
+            old.insert(random_key(&mut rng), vec![rng.gen()]);
+        }
+        let mut new = old.clone();
+        for _ in 0..6 {
+            match rng.gen_range(0..3) {
+                0 => { new.insert(random_key(&mut rng), vec![rng.gen()]); }
+                1 => { let k = *new.keys().nth(rng.gen_range(0..new.len())).unwrap(); new.insert(k, vec![0xEE]); }
+                _ => { let k = *new.keys().nth(rng.gen_range(0..new.len())).unwrap(); new.remove(&k); }
+            }
+        }
+
+        let (a, b) = (build(&kzg, &old), build(&kzg, &new));
+        assert_eq!(a.diff(&b).unwrap(), expected_diff(&old, &new));
+        assert_eq!(b.diff(&a).unwrap(), expected_diff(&new, &old));
+        assert!(a.diff(&a).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn diff_against_empty_tree_lists_every_entry() {
+    let mut rng = StdRng::seed_from_u64(0xBEEFCAFE1234);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let entries: BTreeMap<_, _> = (0..10).map(|_| (random_key(&mut rng), vec![rng.gen()])).collect();
+    let (empty, full) = (build(&kzg, &BTreeMap::new()), build(&kzg, &entries));
+
+    let added = empty.diff(&full).unwrap();
+    assert_eq!(added.len(), entries.len());
+    assert!(added.iter().all(|c| matches!(c, Change::Added { .. })));
+    assert!(added.iter().map(Change::key).eq(entries.keys().copied()));
+    assert!(full.diff(&empty).unwrap().iter().all(|c| matches!(c, Change::Removed { .. })));
+}
+
+#[test]
+fn diff_rejects_uncommitted_trees() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let entries: BTreeMap<_, _> = (0..4).map(|_| (random_key(&mut rng), vec![rng.gen()])).collect();
+    let (mut a, b) = (build(&kzg, &entries), build(&kzg, &entries));
+    let key = random_key(&mut rng);
+    a.insert(key, Value::from(vec![0xEE]));
+    // The stale digests would still match, hiding the insert
+    assert_eq!(a.diff(&b), Err(DiffError::Uncommitted));
+    assert_eq!(b.diff(&a), Err(DiffError::Uncommitted));
+
+    a.commit();
+    assert_eq!(b.diff(&a).unwrap().len(), 1);
+}
@@ -0,0 +1,55 @@
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{dot::DotOptions, KzgVc, Value, VerkleTree};
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_diverging_at(d: usize, b: u8) -> [u8; 31] {
+    let mut s = [0x55u8; 31];
+    s[d] = b;
+    s
+}
+
+#[test]
+fn dot_shows_chain_slots_and_highlighted_path() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+
+    assert_eq!(t.to_dot(&DotOptions::default()).matches(" -> ").count(), 0);
+
+    // Two stems that split at byte 2, so the root grows a two-node Internal chain
+    let k1 = key_from_bytes(stem_diverging_at(2, 0x01), 0x07);
+    let k2 = key_from_bytes(stem_diverging_at(2, 0x02), 0x08);
+    t.insert(k1, Value::from(vec![0xAB; 10]));
+    t.insert(k2, Value::from(vec![0xCD]));
+    t.commit();
+
+    let dot = t.to_dot(&DotOptions::default());
+    assert!(dot.starts_with("digraph verkle {"));
+    assert_eq!(dot.matches("internal\\n").count(), 3);
+    assert_eq!(dot.matches("extension\\n").count(), 2);
+    assert_eq!(dot.matches("label=\"0x55\"").count(), 2);
+    assert!(dot.contains("label=\"0x01\"") && dot.contains("label=\"0x02\""));
+    assert!(dot.contains("0x07: 0xabababab.."));
+    assert!(dot.contains("0x08: 0xcd"));
+    assert!(!dot.contains("digest") && !dot.contains("color=red"));
+
+    let opts = DotOptions { digests: true, highlight: Some(k2) };
+    let dot = t.to_dot(&opts);
+    // Every node but the root shows the digest cached in its parent
+    assert_eq!(dot.matches("\\ndigest 0x").count(), 4);
+    // Root, two chain nodes and k2's Extension, and the three edges between them
+    assert_eq!(dot.matches("color=red").count(), 7);
+
+    // A key that is absent ends at the Extension of a different stem
+    let mut absent_stem = stem_diverging_at(2, 0x01);
+    absent_stem[20] = 0x01;
+    let absent = key_from_bytes(absent_stem, 0);
+    let dot = t.to_dot(&DotOptions { digests: false, highlight: Some(absent) });
+    assert_eq!(dot.matches("color=orange").count(), 1);
+}
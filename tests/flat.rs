@@ -0,0 +1,87 @@
+use std::fs;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{
+    flat::FlatError,
+    hasher::{Blake3Hasher, Sha256Hasher},
+    vc::{verify_proof, Step, VerkleProof},
+    FlatTree, KzgVc, Value, VerkleTree,
+};
+
+fn random_tree(kzg: &KzgVc<'static>, rng: &mut StdRng) -> VerkleTree<KzgVc<'static>> {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for _ in 0..30 {
+        let mut k: [u8; 32] = rng.gen();
+        // Shared prefixes, so paths run through several Internal nodes
+        k[0] = rng.gen_range(0..3);
+        k[1] = rng.gen_range(0..2);
+        k[31] = rng.gen_range(0..4);
+        t.insert(k, Value::from(vec![rng.gen(); rng.gen_range(0..20)]));
+    }
+    t
+}
+
+// Field by field, as the derived equality would need KzgVc itself to be comparable.
+fn same_proof(a: &VerkleProof<KzgVc<'static>>, b: &VerkleProof<KzgVc<'static>>) -> bool {
+    a.value == b.value
+        && a.steps.len() == b.steps.len()
+        && a.steps.iter().zip(&b.steps).all(|pair| match pair {
+            (
+                Step::Internal { parent_commit: c1, index: i1, child_digest: d1, proof: p1 },
+                Step::Internal { parent_commit: c2, index: i2, child_digest: d2, proof: p2 },
+            ) => (c1, i1, d1, p1) == (c2, i2, d2, p2),
+            (Step::Extension { ext_commit: c1, index: i1, proof: p1 }, Step::Extension { ext_commit: c2, index: i2, proof: p2 }) => (c1, i1, p1) == (c2, i2, p2),
+            _ => false,
+        })
+}
+
+#[test]
+fn mapped_file_serves_the_same_values_and_proofs() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = random_tree(&kzg, &mut rng);
+
+    let path = std::env::temp_dir().join(format!("verkle-flat-{}", std::process::id()));
+    let root = tree.write_flat(fs::File::create(&path).unwrap()).unwrap();
+    let flat = FlatTree::<_, KzgVc>::open(&path, kzg.clone()).expect("valid flat tree");
+    assert_eq!(flat.root(), root);
+
+    for (k, v) in tree.iter() {
+        assert_eq!(flat.get(k).as_ref(), Some(v));
+        let proof = flat.prove_get(k).expect("stored key");
+        assert!(same_proof(&proof, &tree.prove_get(k).unwrap()));
+        assert!(verify_proof(&kzg, &root, &proof, k));
+    }
+
+    // Absent stems, and absent slots of a stored stem
+    let (mut k, _) = tree.iter().next().unwrap();
+    k[31] = 200;
+    for key in [k, [0xEE; 32], [0u8; 32]] {
+        assert_eq!(flat.get(key), tree.get(key));
+        assert!(flat.prove_get(key).is_none());
+    }
+    drop(flat);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn empty_and_mismatched_files() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let mut empty = VerkleTree::<KzgVc>::new(kzg.clone());
+    let mut bytes = Vec::new();
+    let root = empty.write_flat(&mut bytes).unwrap();
+    let flat = FlatTree::<_, KzgVc>::new(&bytes[..], kzg.clone()).expect("empty flat tree");
+    assert_eq!(flat.root(), root);
+    assert_eq!(flat.get([0u8; 32]), None);
+
+    let mut tree = random_tree(&kzg, &mut rng);
+    let mut bytes = Vec::new();
+    tree.write_flat(&mut bytes).unwrap();
+    assert!(matches!(FlatTree::<_, KzgVc, Sha256Hasher>::new(&bytes[..], kzg.clone()), Err(FlatError::Incompatible("hash function"))));
+    let narrow = KzgVc::<'static, 16>::setup(&mut rng).unwrap();
+    assert!(matches!(FlatTree::<_, KzgVc<'static, 16>, Blake3Hasher, 16>::new(&bytes[..], narrow), Err(FlatError::Incompatible("width"))));
+    assert!(matches!(FlatTree::<_, KzgVc>::new(&bytes[..bytes.len() - 1], kzg.clone()), Err(FlatError::Format(_))));
+    assert!(matches!(FlatTree::<_, KzgVc>::new(&b"VRKS"[..], kzg), Err(FlatError::Format(_))));
+}
@@ -0,0 +1,117 @@
+use ark_ff::{Fp64, MontBackend, MontConfig, PrimeField};
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{
+    hasher::{Blake3Hasher, DomainSeparated, HashDomain, PoseidonHasher, Sha256Hasher, Wide},
+    vc::{verify_proof, VectorCommitment},
+    KzgVc, TreeHasher, Value, VerkleTree,
+};
+
+// The largest 64-bit prime, a field unrelated to the KZG backend.
+#[derive(MontConfig)]
+#[modulus = "18446744073709551557"]
+#[generator = "2"]
+struct SmallConfig;
+type Small = Fp64<MontBackend<SmallConfig, 1>>;
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_repeat(b: u8) -> [u8; 31] {
+    let mut s = [0u8; 31];
+    s.fill(b);
+    s
+}
+
+// Builds a small tree with hasher H, checks every proof and returns the root.
+fn root_with<H: TreeHasher>(kzg: &KzgVc<'static>) -> <KzgVc<'static> as VectorCommitment>::Commitment {
+    let mut tree = VerkleTree::<KzgVc, H>::new(kzg.clone());
+    let keys = [
+        key_from_bytes(stem_repeat(1), 2),
+        key_from_bytes(stem_repeat(1), 3),
+        key_from_bytes(stem_repeat(9), 2),
+    ];
+    for (i, k) in keys.iter().enumerate() {
+        tree.insert(*k, Value::from(vec![i as u8; 4]));
+    }
+    let root = tree.commit();
+    for k in keys {
+        let proof = tree.prove_get(k).unwrap();
+        assert!(verify_proof(kzg, &root, &proof, k));
+    }
+    root
+}
+
+#[test]
+fn every_hasher_proves_and_gives_distinct_roots() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let roots = [
+        root_with::<Blake3Hasher>(&kzg),
+        root_with::<Sha256Hasher>(&kzg),
+        root_with::<PoseidonHasher>(&kzg),
+        root_with::<Wide<Blake3Hasher>>(&kzg),
+        root_with::<Wide<Sha256Hasher>>(&kzg),
+        root_with::<DomainSeparated<Blake3Hasher>>(&kzg),
+        root_with::<DomainSeparated<PoseidonHasher>>(&kzg),
+        root_with::<Wide<DomainSeparated<Blake3Hasher>>>(&kzg),
+    ];
+    for (i, a) in roots.iter().enumerate() {
+        for b in &roots[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
+
+#[test]
+fn default_hasher_is_blake3() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+
+    let key = key_from_bytes(stem_repeat(1), 2);
+    let mut default_tree = VerkleTree::<KzgVc>::new(kzg.clone());
+    let mut blake_tree = VerkleTree::<KzgVc, Blake3Hasher>::new(kzg);
+    default_tree.insert(key, Value::from(vec![1]));
+    blake_tree.insert(key, Value::from(vec![1]));
+    assert_eq!(default_tree.commit(), blake_tree.commit());
+}
+
+#[test]
+fn wide_blake3_uses_extended_output() {
+    let wide = Blake3Hasher::hash_wide(b"verkle");
+    assert_eq!(wide[..32], Blake3Hasher::hash(b"verkle"));
+    assert_ne!(wide[32..], [0u8; 32]);
+}
+
+#[test]
+fn domain_tags_are_distinct_and_equal_length() {
+    let domains = [HashDomain::InternalChild, HashDomain::ExtensionSlot, HashDomain::EmptyChild, HashDomain::EmptySlot];
+    let tags: Vec<_> = domains.iter().map(|d| DomainSeparated::<Blake3Hasher>::domain_prefix(*d)).collect();
+    for (i, a) in tags.iter().enumerate() {
+        assert_eq!(a.len(), tags[0].len());
+        for b in &tags[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+
+    // Scheme version 0 keeps the untagged digests, and adapters preserve the scheme.
+    assert!(domains.iter().all(|d| Blake3Hasher::domain_prefix(*d).is_empty()));
+    assert_eq!(Blake3Hasher::SCHEME_VERSION, 0);
+    assert_eq!(DomainSeparated::<Blake3Hasher>::SCHEME_VERSION, 1);
+    assert_eq!(Wide::<DomainSeparated<Blake3Hasher>>::SCHEME_VERSION, 1);
+    assert_eq!(Wide::<DomainSeparated<Blake3Hasher>>::domain_prefix(HashDomain::EmptySlot), tags[3]);
+}
+
+#[test]
+fn poseidon_hashes_over_its_own_field() {
+    let small = PoseidonHasher::<Small>::hash(b"verkle");
+    assert_ne!(small, <PoseidonHasher>::hash(b"verkle"));
+    // The digest is an element of the small field, recovered exactly by hash_to_field
+    assert_eq!(small[8..], [0u8; 24]);
+    let element: Small = PoseidonHasher::<Small>::hash_to_field(b"verkle");
+    assert_eq!(element.into_bigint().0[0].to_le_bytes(), small[..8]);
+}
@@ -1,4 +1,4 @@
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use verkle::{KzgVc, Value, VerkleTree};
 
 fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
@@ -23,10 +23,10 @@ fn insert_then_get_single() {
     let stem = stem_repeat(0xAB);
     let k = key_from_bytes(stem, 0x01);
 
-    t.insert(k, Value(b"hello".to_vec()));
+    t.insert(k, Value::from(b"hello".to_vec()));
     let got = t.get(k).expect("should find inserted value");
 
-    assert_eq!(got.0, b"hello");
+    assert_eq!(&got.0[..], b"hello");
 }
 
 #[test]
@@ -39,11 +39,11 @@ fn insert_shared_stem_two_suffixes() {
     let k0 = key_from_bytes(stem, 0x00);
     let kf = key_from_bytes(stem, 0xFF);
 
-    t.insert(k0, Value(b"A".to_vec()));
-    t.insert(kf, Value(b"B".to_vec()));
+    t.insert(k0, Value::from(b"A".to_vec()));
+    t.insert(kf, Value::from(b"B".to_vec()));
 
-    assert_eq!(t.get(k0).unwrap().0, b"A");
-    assert_eq!(t.get(kf).unwrap().0, b"B");
+    assert_eq!(&t.get(k0).unwrap().0[..], b"A");
+    assert_eq!(&t.get(kf).unwrap().0[..], b"B");
 }
 
 #[test]
@@ -60,11 +60,11 @@ fn split_at_root_two_different_stems() {
     let k1 = key_from_bytes(s1, 0x01);
     let k2 = key_from_bytes(s2, 0xF0);
 
-    t.insert(k1, Value(b"one".to_vec()));
-    t.insert(k2, Value(b"two".to_vec()));
+    t.insert(k1, Value::from(b"one".to_vec()));
+    t.insert(k2, Value::from(b"two".to_vec()));
 
-    assert_eq!(t.get(k1).unwrap().0, b"one");
-    assert_eq!(t.get(k2).unwrap().0, b"two");
+    assert_eq!(&t.get(k1).unwrap().0[..], b"one");
+    assert_eq!(&t.get(k2).unwrap().0[..], b"two");
 }
 
 #[test]
@@ -89,11 +89,11 @@ fn split_deep_divergence_both_retrievable() {
     let k1 = key_from_bytes(s1, 0x01);
     let k2 = key_from_bytes(s2, 0xF0);
 
-    t.insert(k1, Value(b"left".to_vec()));
-    t.insert(k2, Value(b"right".to_vec()));
+    t.insert(k1, Value::from(b"left".to_vec()));
+    t.insert(k2, Value::from(b"right".to_vec()));
 
-    assert_eq!(t.get(k1).unwrap().0, b"left");
-    assert_eq!(t.get(k2).unwrap().0, b"right");
+    assert_eq!(&t.get(k1).unwrap().0[..], b"left");
+    assert_eq!(&t.get(k2).unwrap().0[..], b"right");
 }
 
 #[test]
@@ -103,12 +103,12 @@ fn split_early_extension() {
     let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
 
     let key1 = key_from_bytes(stem_repeat(1), 2);
-    let value1 = Value(vec![3, 4, 5]);
+    let value1 = Value::from(vec![3, 4, 5]);
     tree.insert(key1, value1);
 
     let mut key2 = key_from_bytes(stem_repeat(1), 3); // Different stem diverges at byte 5
     key2[2] = 99;
-    let value2 = Value(vec![6, 7, 8]);
+    let value2 = Value::from(vec![6, 7, 8]);
     tree.insert(key2, value2);
 
     assert_eq!(*tree.get(key1).unwrap().0, vec![3, 4, 5]);
@@ -125,11 +125,11 @@ fn same_stem_multiple_writes_overwrite_slot() {
     let stem = stem_repeat(0x77);
     let k = key_from_bytes(stem, 0x2A);
 
-    t.insert(k, Value(b"first".to_vec()));
-    assert_eq!(t.get(k).unwrap().0, b"first");
+    t.insert(k, Value::from(b"first".to_vec()));
+    assert_eq!(&t.get(k).unwrap().0[..], b"first");
 
-    t.insert(k, Value(b"second".to_vec()));
-    assert_eq!(t.get(k).unwrap().0, b"second");
+    t.insert(k, Value::from(b"second".to_vec()));
+    assert_eq!(&t.get(k).unwrap().0[..], b"second");
 }
 
 #[test]
@@ -142,9 +142,51 @@ fn different_suffixes_same_stem_do_not_clobber_each_other() {
     let k_a = key_from_bytes(stem, 0x0A);
     let k_b = key_from_bytes(stem, 0x0B);
 
-    t.insert(k_a, Value(b"A".to_vec()));
-    t.insert(k_b, Value(b"B".to_vec()));
+    t.insert(k_a, Value::from(b"A".to_vec()));
+    t.insert(k_b, Value::from(b"B".to_vec()));
 
-    assert_eq!(t.get(k_a).unwrap().0, b"A");
-    assert_eq!(t.get(k_b).unwrap().0, b"B");
+    assert_eq!(&t.get(k_a).unwrap().0[..], b"A");
+    assert_eq!(&t.get(k_b).unwrap().0[..], b"B");
+}
+
+#[test]
+fn get_and_prove_share_the_stored_buffer() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut t = VerkleTree::<KzgVc>::new(kzg);
+
+    let k = key_from_bytes(stem_repeat(0x44), 0x01);
+    let value = Value::from(vec![7u8; 1024]);
+    let ptr = value.0.as_ptr();
+    t.insert(k, value);
+    t.commit();
+
+    assert_eq!(t.get(k).unwrap().0.as_ptr(), ptr);
+    assert_eq!(t.prove_get(k).unwrap().value.as_ptr(), ptr);
+}
+
+#[test]
+fn iter_yields_entries_in_key_order() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg);
+
+    let mut keys: Vec<[u8; 32]> = (0..40)
+        .map(|_| {
+            let mut k: [u8; 32] = rng.gen();
+            // Share a prefix now and then so that Internal chains appear too
+            k[..2].fill(rng.gen_range(0..3));
+            k
+        })
+        .collect();
+    keys.shuffle(&mut rng);
+    for k in &keys {
+        tree.insert(*k, Value::from(k[..4].to_vec()));
+    }
+
+    keys.sort();
+    keys.dedup();
+    let got: Vec<_> = tree.iter().map(|(k, v)| (k, v.clone())).collect();
+    let expected: Vec<_> = keys.iter().map(|k| (*k, Value::from(k[..4].to_vec()))).collect();
+    assert_eq!(got, expected);
 }
@@ -0,0 +1,65 @@
+use ark_bls12_381::Fr;
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{
+    kzg::{native, Commitment, Proof},
+    vc::VectorCommitment,
+    KzgVc,
+};
+
+// Re-encodes a value as the other backend's type; both use the same encoding.
+fn convert<T: CanonicalSerialize, U: CanonicalDeserialize>(value: &T) -> U {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).expect("serialize");
+    U::deserialize_compressed(&bytes[..]).expect("same encoding")
+}
+
+// Checks that the native backend agrees with ark-poly-commit under the same setup.
+fn check_agreement<const W: usize>(ark: &KzgVc<'static, W>, ours: &native::KzgVc<'static, W>, rng: &mut StdRng) {
+    let children: [Fr; W] = std::array::from_fn(|_| Fr::rand(rng));
+    let comm = ark.commit_from_children(&children);
+    assert_eq!(convert::<_, native::Commitment>(&comm), ours.commit_from_children(&children));
+
+    for index in [0, 1, W / 2, W - 1] {
+        let (value, proof) = ark.open_at(&children, index);
+        let (our_value, our_proof) = ours.open_at(&children, index);
+        assert_eq!(value, our_value);
+        assert_eq!(convert::<_, native::Proof>(&proof), our_proof);
+
+        // Each backend accepts the other's openings and rejects the same forgeries
+        assert!(ours.verify_at(&convert(&comm), index, value, &convert(&proof)));
+        assert!(ark.verify_at(&comm, index, value, &convert(&our_proof)));
+        let wrong = value + Fr::from(1u64);
+        assert!(!ours.verify_at(&convert(&comm), index, wrong, &convert(&proof)));
+        assert!(!ark.verify_at(&comm, index, wrong, &proof));
+        let other = (index + 1) % W;
+        assert!(!ours.verify_at(&convert(&comm), other, value, &convert(&proof)));
+        assert!(!ark.verify_at(&comm, other, value, &proof));
+
+        // Hiding openings are never produced, so both reject them
+        let hiding = Proof { random_v: Some(Fr::rand(rng)), ..proof };
+        assert!(!ark.verify_at(&comm, index, value, &hiding));
+        assert!(!ours.verify_at(&convert(&comm), index, value, &convert(&hiding)));
+    }
+
+    let (old, proof) = ark.open_at(&children, 3);
+    let new = Fr::rand(rng);
+    let updated: Commitment = ark.update_at(&comm, &[(3, old, new, &proof)]).expect("valid opening");
+    let ours_updated = ours.update_at(&convert(&comm), &[(3, old, new, &convert(&proof))]).expect("valid opening");
+    assert_eq!(convert::<_, native::Commitment>(&updated), ours_updated);
+}
+
+#[test]
+fn native_kzg_matches_ark_poly_commit() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+
+    let ark = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let ours = native::KzgVc::from_bytes(&ark.to_bytes()).expect("setups share an encoding");
+    check_agreement::<256>(&ark, &ours, &mut rng);
+
+    let ours = native::KzgVc::<16>::setup(&mut rng).expect("KZG setup should not fail");
+    let ark = KzgVc::from_bytes(&ours.to_bytes()).expect("setups share an encoding");
+    assert_eq!(ark.to_bytes(), ours.to_bytes());
+    check_agreement::<16>(&ark, &ours, &mut rng);
+}
@@ -27,9 +27,9 @@ fn stems_differ_only_at_last_byte_both_present() {
     let k1 = key_from_bytes(s1, 0x00);
     let k2 = key_from_bytes(s2, 0xFF);
 
-    t.insert(k1, Value(b"v1".to_vec()));
-    t.insert(k2, Value(b"v2".to_vec()));
+    t.insert(k1, Value::from(b"v1".to_vec()));
+    t.insert(k2, Value::from(b"v2".to_vec()));
 
-    assert_eq!(t.get(k1).unwrap().0, b"v1");
-    assert_eq!(t.get(k2).unwrap().0, b"v2");
+    assert_eq!(&t.get(k1).unwrap().0[..], b"v1");
+    assert_eq!(&t.get(k2).unwrap().0[..], b"v2");
 }
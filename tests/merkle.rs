@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use ark_bls12_381::Fr;
+use ark_ff::UniformRand;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{
+    compact::{verify_compact_proof, CompactProof},
+    hasher::Blake3Hasher,
+    vc::{verify_proof, Step, VectorCommitment},
+    MerkleVc, Value, VerkleTree,
+};
+
+fn random_tree(rng: &mut StdRng) -> VerkleTree<MerkleVc> {
+    let mut t = VerkleTree::<MerkleVc>::new(MerkleVc);
+    for _ in 0..300 {
+        let mut k: [u8; 32] = rng.gen();
+        // Shared prefixes, so proofs have several Internal steps
+        k[0] = rng.gen_range(0..3);
+        k[1] = rng.gen_range(0..2);
+        k[31] = rng.gen_range(0..4);
+        t.insert(k, Value::from(vec![rng.gen(); rng.gen_range(0..20)]));
+    }
+    t
+}
+
+#[test]
+fn merkle_backed_tree_proofs_verify() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut tree = random_tree(&mut rng);
+    let root = tree.commit();
+
+    for (k, v) in tree.iter() {
+        let proof = tree.prove_get(k).expect("stored key");
+        assert_eq!(proof.value, v.0);
+        // One 8-hash authentication path per step
+        assert!(proof.steps.iter().all(|step| match step {
+            Step::Internal { proof, .. } | Step::Extension { proof, .. } => proof.len() == 8,
+        }));
+        assert!(verify_proof(&MerkleVc, &root, &proof, k));
+        let compact = CompactProof::<MerkleVc>::from_bytes(&proof.compact().to_bytes()).expect("valid encoding");
+        assert!(verify_compact_proof(&MerkleVc, &root, &compact, k));
+    }
+
+    // A narrow tree has 4-hash paths
+    let mut narrow = VerkleTree::<MerkleVc<16>, Blake3Hasher, 16>::new(MerkleVc);
+    let entries: BTreeMap<_, _> = (0..50u8)
+        .map(|i| {
+            let mut k = [0u8; 32];
+            (k[0], k[1], k[31]) = (i % 5, i, i % 16);
+            (k, Value::from(vec![i]))
+        })
+        .collect();
+    narrow.insert_batch(entries);
+    let narrow_root = narrow.commit();
+    for (k, _) in narrow.iter() {
+        let proof = narrow.prove_get(k).unwrap();
+        assert!(verify_proof(&MerkleVc, &narrow_root, &proof, k));
+    }
+}
+
+#[test]
+fn tampered_merkle_proofs_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut tree = random_tree(&mut rng);
+    let root = tree.commit();
+    let (k, _) = tree.iter().nth(42).unwrap();
+    let proof = tree.prove_get(k).unwrap();
+
+    let mut wrong_value = proof.clone();
+    wrong_value.value = b"forged".to_vec().into();
+    assert!(!verify_proof(&MerkleVc, &root, &wrong_value, k));
+
+    for step in 0..proof.steps.len() {
+        let mut tampered = proof.clone();
+        let (Step::Internal { proof: path, .. } | Step::Extension { proof: path, .. }) = &mut tampered.steps[step];
+        path[3][0] ^= 1;
+        assert!(!verify_proof(&MerkleVc, &root, &tampered, k));
+    }
+
+    let mut short = proof.clone();
+    let (Step::Internal { proof: path, .. } | Step::Extension { proof: path, .. }) = &mut short.steps[0];
+    path.pop();
+    assert!(!verify_proof(&MerkleVc, &root, &short, k));
+}
+
+#[test]
+fn merkle_updates_match_a_recommit() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let vc = MerkleVc::<256>;
+    let mut children: [Fr; 256] = std::array::from_fn(|_| Fr::rand(&mut rng));
+    let comm = vc.commit_from_children(&children);
+
+    // Siblings, and an index far away, so the paths overlap to different depths
+    let indices = [3, 2, 200, 7];
+    let openings: Vec<_> = indices.iter().map(|&i| vc.open_at(&children, i)).collect();
+    let news: Vec<_> = indices.iter().map(|_| Fr::rand(&mut rng)).collect();
+    let updates: Vec<_> = indices.iter().zip(&openings).zip(&news).map(|((&i, (old, proof)), &new)| (i, *old, new, proof)).collect();
+    let updated = vc.update_at(&comm, &updates).expect("valid openings");
+    for (&i, &new) in indices.iter().zip(&news) {
+        children[i] = new;
+    }
+    assert_eq!(updated, vc.commit_from_children(&children));
+
+    assert!(!vc.verify_at(&comm, 256, openings[0].0, &openings[0].1));
+    assert!(vc.update_at(&comm, &[(3, news[0], openings[0].0, &openings[0].1)]).is_none());
+}
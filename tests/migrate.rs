@@ -0,0 +1,142 @@
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{
+    hasher::Blake3Hasher,
+    migrate::{migrate, tree_key, MigrationError, Overlay, StateDump, StateItem},
+    vc::verify_proof,
+    KzgVc, Value,
+};
+
+const A: [u8; 20] = [0x11; 20];
+const B: [u8; 20] = [0x22; 20];
+const C: [u8; 20] = [0x33; 20];
+
+// PUSH32 at offset 0 carries its data two bytes into the second chunk.
+fn dump_json() -> String {
+    let code = format!("0x7f{}00", "ab".repeat(32));
+    format!(
+        r#"{{
+            "root": "0x{root}",
+            "accounts": {{
+                "0x{a}": {{ "balance": "1000000000000000000000", "nonce": 7, "codeHash": "0x{hash}", "code": "{code}",
+                            "storage": {{ "0x05": "0x2a", "0x0100": "0x01" }} }},
+                "0x{b}": {{ "balance": "0x10", "nonce": 0 }},
+                "0x{c}": {{ "balance": "3", "nonce": "2" }}
+            }}
+        }}"#,
+        root = "cd".repeat(32),
+        a = "11".repeat(20),
+        b = "22".repeat(20),
+        c = "33".repeat(20),
+        hash = "ef".repeat(32),
+    )
+}
+
+fn le(n: u128) -> Value {
+    let mut word = [0u8; 32];
+    word[..16].copy_from_slice(&n.to_le_bytes());
+    Value::from(word.to_vec())
+}
+
+fn key(address: &[u8; 20], item: StateItem) -> [u8; 32] {
+    tree_key::<Blake3Hasher>(address, item)
+}
+
+#[test]
+fn keys_follow_the_account_layout() {
+    let header = key(&A, StateItem::Version);
+    let stem = &header[..31];
+    let slot = |n: u16| {
+        let mut s = [0u8; 32];
+        s[30..].copy_from_slice(&n.to_be_bytes());
+        StateItem::Storage(s)
+    };
+
+    for (item, suffix) in [(StateItem::Balance, 1), (StateItem::CodeSize, 4), (slot(5), 69), (StateItem::CodeChunk(0), 128), (StateItem::CodeChunk(127), 255)] {
+        let k = key(&A, item);
+        assert_eq!((&k[..31], k[31]), (stem, suffix), "{item:?}");
+    }
+    // Slots from 64 on, and code past the header, live in other stems
+    assert_ne!(&key(&A, slot(64))[..31], stem);
+    assert_eq!(key(&A, slot(0x0100))[31], 0x00);
+    assert_eq!(&key(&A, slot(0x0100))[..31], &key(&A, slot(0x01ff))[..31]);
+    assert_ne!(&key(&A, StateItem::CodeChunk(128))[..31], stem);
+    assert_ne!(&key(&B, StateItem::Version)[..31], stem);
+}
+
+#[test]
+fn json_dump_migrates_with_report() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let dump = StateDump::from_json(&dump_json()).expect("valid dump");
+
+    let (tree, report) = migrate::<_, Blake3Hasher>(kzg.clone(), &dump);
+    assert_eq!(report.mpt_root, Some([0xCD; 32]));
+    assert_eq!((report.accounts, report.storage_slots, report.code_chunks), (3, 2, 2));
+    assert_eq!(report.keys, 3 * 5 + 2 + 2);
+    assert_eq!(tree.iter().count(), report.keys);
+
+    assert_eq!(tree.get(key(&A, StateItem::Balance)), Some(le(1_000_000_000_000_000_000_000)));
+    assert_eq!(tree.get(key(&A, StateItem::Nonce)), Some(le(7)));
+    assert_eq!(tree.get(key(&A, StateItem::CodeSize)), Some(le(34)));
+    assert_eq!(tree.get(key(&B, StateItem::Balance)), Some(le(16)));
+    assert_eq!(tree.get(key(&C, StateItem::Nonce)), Some(le(2)));
+
+    let mut slot = [0u8; 32];
+    slot[31] = 5;
+    let mut value = vec![0u8; 32];
+    value[31] = 0x2a;
+    assert_eq!(tree.get(key(&A, StateItem::Storage(slot))), Some(Value::from(value)));
+
+    // The second chunk starts with two bytes of PUSH32 data
+    let chunk = tree.get(key(&A, StateItem::CodeChunk(1))).unwrap();
+    assert_eq!(&chunk.0[..4], &[2, 0xab, 0xab, 0x00]);
+
+    let k = key(&A, StateItem::CodeHash);
+    let proof = tree.prove_get(k).unwrap();
+    assert!(verify_proof(&kzg, &report.verkle_root, &proof, k));
+}
+
+#[test]
+fn overlay_reads_fall_back_until_converted() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let dump = StateDump::from_json(&dump_json()).expect("valid dump");
+    let (mut migrated, _) = migrate::<_, Blake3Hasher>(kzg.clone(), &dump);
+
+    let mut overlay = Overlay::<KzgVc, Blake3Hasher>::new(kzg, dump);
+    assert_eq!(overlay.get(&B, StateItem::Balance), Some(le(16)));
+    assert!(!overlay.is_converted(&B));
+
+    // A write during the transition wins over the old state once C is converted
+    overlay.insert(&C, StateItem::Balance, le(99));
+    assert_eq!(overlay.get(&C, StateItem::Balance), Some(le(99)));
+
+    assert_eq!(overlay.convert(2), 2);
+    assert!(overlay.is_converted(&A) && overlay.is_converted(&B) && !overlay.is_converted(&C));
+    assert_eq!(overlay.get(&B, StateItem::Balance), Some(le(16)));
+    assert_eq!(overlay.convert(2), 1);
+    assert!(overlay.is_done());
+    assert_eq!(overlay.convert(2), 0);
+    assert_eq!(overlay.get(&C, StateItem::Balance), Some(le(99)));
+    assert_eq!(overlay.get(&C, StateItem::Nonce), Some(le(2)));
+    assert_eq!(overlay.get(&[0x44; 20], StateItem::Balance), None);
+
+    migrated.insert(key(&C, StateItem::Balance), le(99));
+    assert_eq!(overlay.tree_mut().commit(), migrated.commit());
+}
+
+#[test]
+fn malformed_dumps_are_rejected() {
+    assert!(matches!(StateDump::from_json("not json"), Err(MigrationError::Json(_))));
+    assert!(matches!(StateDump::from_json("{}"), Err(MigrationError::Json(_))));
+
+    let bad_balance = format!(r#"{{"accounts": {{"0x{}": {{"balance": "12x"}}}}}}"#, "11".repeat(20));
+    assert!(matches!(StateDump::from_json(&bad_balance), Err(MigrationError::Field { field: "balance", .. })));
+
+    let bad_address = r#"{"accounts": {"0x1234": {"balance": "1"}}}"#;
+    assert!(matches!(StateDump::from_json(bad_address), Err(MigrationError::Field { field: "address", .. })));
+
+    // Non-ASCII digits are rejected, not sliced through
+    let bad_code = format!(r#"{{"accounts": {{"0x{}": {{"code": "0xé0"}}}}}}"#, "11".repeat(20));
+    assert!(matches!(StateDump::from_json(&bad_code), Err(MigrationError::Field { field: "code", .. })));
+}
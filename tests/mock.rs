@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use ark_bls12_381::Fr;
+use ark_ff::UniformRand;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use verkle::{
+    compact::{verify_compact_proof, CompactProof},
+    hasher::Blake3Hasher,
+    vc::{verify_proof, VectorCommitment},
+    MockVc, Value, VerkleTree,
+};
+
+fn random_entries(rng: &mut StdRng, count: usize) -> Vec<([u8; 32], Value)> {
+    (0..count)
+        .map(|_| {
+            let mut k: [u8; 32] = rng.gen();
+            // Shared prefixes, so the tree splits several levels deep
+            k[0] = rng.gen_range(0..4);
+            k[1] = rng.gen_range(0..4);
+            k[31] = rng.gen_range(0..8);
+            (k, Value::from(vec![rng.gen(); rng.gen_range(0..20)]))
+        })
+        .collect()
+}
+
+#[test]
+fn large_trees_commit_and_prove_with_the_mock() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let entries: BTreeMap<_, _> = random_entries(&mut rng, 5000).into_iter().collect();
+    let mut tree = VerkleTree::<MockVc>::new(MockVc);
+    tree.insert_batch(entries.clone());
+    let root = tree.commit();
+    assert!(tree.is_canonical());
+
+    // Same root for any insertion order
+    let mut shuffled: Vec<_> = entries.into_iter().collect();
+    shuffled.shuffle(&mut rng);
+    let mut other = VerkleTree::<MockVc>::new(MockVc);
+    for (k, v) in shuffled {
+        other.insert(k, v);
+    }
+    assert_eq!(other.commit(), root);
+
+    for (k, v) in tree.iter() {
+        let proof = tree.prove_get(k).expect("stored key");
+        assert_eq!(proof.value, v.0);
+        assert!(verify_proof(&MockVc, &root, &proof, k));
+        let compact = CompactProof::<MockVc>::from_bytes(&proof.compact().to_bytes()).expect("valid encoding");
+        assert!(verify_compact_proof(&MockVc, &root, &compact, k));
+    }
+
+    // Suffixes from random_entries are below 8, so they all fit
+    let mut narrow = VerkleTree::<MockVc<16>, Blake3Hasher, 16>::new(MockVc);
+    narrow.insert_batch(random_entries(&mut rng, 500));
+    let narrow_root = narrow.commit();
+    for (k, _) in narrow.iter().step_by(7) {
+        assert!(verify_proof(&MockVc, &narrow_root, &narrow.prove_get(k).unwrap(), k));
+    }
+}
+
+#[test]
+fn mock_proofs_are_still_binding() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut tree = VerkleTree::<MockVc>::new(MockVc);
+    tree.insert_batch(random_entries(&mut rng, 200));
+    let root = tree.commit();
+    let (k, _) = tree.iter().nth(17).unwrap();
+    let proof = tree.prove_get(k).unwrap();
+
+    let mut wrong_value = proof.clone();
+    wrong_value.value = b"forged".to_vec().into();
+    assert!(!verify_proof(&MockVc, &root, &wrong_value, k));
+
+    let (other, _) = tree.iter().nth(18).unwrap();
+    assert!(!verify_proof(&MockVc, &root, &proof, other));
+
+    tree.insert(k, Value::from(&b"changed"[..]));
+    assert!(!verify_proof(&MockVc, &tree.commit(), &proof, k));
+}
+
+#[test]
+fn mock_updates_match_a_recommit() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let vc = MockVc::<256>;
+    let mut children: [Fr; 256] = std::array::from_fn(|_| Fr::rand(&mut rng));
+    let comm = vc.commit_from_children(&children);
+    let (old_a, proof_a) = vc.open_at(&children, 3);
+    let (old_b, proof_b) = vc.open_at(&children, 200);
+    assert!(vc.verify_at(&comm, 3, old_a, &proof_a));
+    assert!(!vc.verify_at(&comm, 4, old_a, &proof_a));
+    assert!(!vc.verify_at(&comm, 256, old_a, &proof_a));
+
+    let (new_a, new_b) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+    let updated = vc.update_at(&comm, &[(3, old_a, new_a, &proof_a), (200, old_b, new_b, &proof_b)]).expect("valid openings");
+    children[3] = new_a;
+    children[200] = new_b;
+    assert_eq!(updated, vc.commit_from_children(&children));
+    assert!(vc.update_at(&comm, &[(3, new_a, old_a, &proof_a)]).is_none());
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use proptest::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use verkle::{vc::verify_proof, KzgVc, Value, VerkleTree};
+
+fn kzg() -> KzgVc<'static> {
+    static KZG: OnceLock<KzgVc<'static>> = OnceLock::new();
+    KZG.get_or_init(|| {
+        let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+        KzgVc::setup(&mut rng).expect("KZG setup should not fail")
+    })
+    .clone()
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert([u8; 32], Vec<u8>),
+    Remove([u8; 32]),
+    Get([u8; 32]),
+    Commit,
+    Prove([u8; 32]),
+}
+
+// Keys are drawn from a small alphabet so that sequences hit shared stems,
+// overwrites and splits at the first, a middle and the last stem byte.
+fn key_strategy() -> impl Strategy<Value = [u8; 32]> {
+    (0..2u8, prop::sample::select(vec![1usize, 15, 30]), 0..3u8, prop::sample::select(vec![0x00u8, 0x01, 0xFF]))
+        .prop_map(|(first, depth, byte, suffix)| {
+            let mut k = [0x11u8; 32];
+            k[0] = first;
+            k[depth] = byte;
+            k[31] = suffix;
+            k
+        })
+}
+
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (key_strategy(), prop::collection::vec(any::<u8>(), 0..4)).prop_map(|(k, v)| Op::Insert(k, v)),
+        2 => key_strategy().prop_map(Op::Remove),
+        2 => key_strategy().prop_map(Op::Get),
+        1 => Just(Op::Commit),
+        1 => key_strategy().prop_map(Op::Prove),
+    ]
+}
+
+fn build(entries: &[([u8; 32], Vec<u8>)]) -> VerkleTree<KzgVc<'static>> {
+    let mut t = VerkleTree::<KzgVc>::new(kzg());
+    for (k, v) in entries {
+        t.insert(*k, Value::from(v.clone()));
+    }
+    t
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn tree_matches_btreemap_model(ops in prop::collection::vec(op_strategy(), 1..16), seed in any::<u64>()) {
+        let vc = kzg();
+        let mut t = VerkleTree::<KzgVc>::new(vc.clone());
+        let mut model: BTreeMap<[u8; 32], Vec<u8>> = BTreeMap::new();
+
+        for op in &ops {
+            match op {
+                Op::Insert(k, v) => {
+                    t.insert(*k, Value::from(v.clone()));
+                    model.insert(*k, v.clone());
+                }
+                Op::Remove(k) => {
+                    prop_assert_eq!(t.remove(*k).map(|v| v.0.to_vec()), model.remove(k));
+                    prop_assert!(t.is_canonical());
+                }
+                Op::Get(k) => {
+                    prop_assert_eq!(t.get(*k).map(|v| v.0.to_vec()), model.get(k).cloned());
+                }
+                Op::Commit => {
+                    t.commit();
+                }
+                Op::Prove(k) => {
+                    // Proofs are produced from cached commitments, so refresh them first.
+                    let root = t.commit();
+                    match (t.prove_get(*k), model.get(k)) {
+                        (Some(proof), Some(v)) => {
+                            prop_assert_eq!(&proof.value, v);
+                            prop_assert!(verify_proof(&vc, &root, &proof, *k));
+                        }
+                        (None, None) => {}
+                        (got, want) => prop_assert!(false, "prove_get returned {:?}, model has {:?}", got.map(|p| p.value), want),
+                    }
+                }
+            }
+        }
+
+        // Invariant: every present key has a valid proof against the final root.
+        let root = t.commit();
+        for (k, v) in &model {
+            let proof = t.prove_get(*k).expect("present key must be provable");
+            prop_assert_eq!(&proof.value, v);
+            prop_assert!(verify_proof(&vc, &root, &proof, *k));
+        }
+
+        // Invariant: the root depends only on the key/value set, not on insertion order.
+        let mut entries: Vec<_> = model.into_iter().collect();
+        entries.shuffle(&mut StdRng::seed_from_u64(seed));
+        prop_assert_eq!(build(&entries).commit(), root);
+    }
+}
@@ -0,0 +1,123 @@
+use bytes::Bytes;
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{partial::PartialTreeError, vc::VectorCommitment, KzgVc, MockVc, PartialTree, Value, VerkleTree};
+
+// MockVc without its update_at, as a scheme that cannot update from openings would be.
+#[derive(Clone)]
+struct NoUpdates(MockVc);
+
+impl VectorCommitment for NoUpdates {
+    type Fr = <MockVc as VectorCommitment>::Fr;
+    type Commitment = <MockVc as VectorCommitment>::Commitment;
+    type Proof = <MockVc as VectorCommitment>::Proof;
+
+    fn commit_from_children(&self, children: &[Self::Fr; 256]) -> Self::Commitment {
+        self.0.commit_from_children(children)
+    }
+
+    fn open_at(&self, children: &[Self::Fr; 256], index: usize) -> (Self::Fr, Self::Proof) {
+        self.0.open_at(children, index)
+    }
+
+    fn verify_at(&self, commitment: &Self::Commitment, index: usize, value_digest: Self::Fr, proof: &Self::Proof) -> bool {
+        self.0.verify_at(commitment, index, value_digest, proof)
+    }
+}
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_repeat(b: u8) -> [u8; 31] {
+    let mut s = [0u8; 31];
+    s.fill(b);
+    s
+}
+
+#[test]
+fn stateless_update_matches_full_tree() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
+
+    let mut deep = stem_repeat(1);
+    deep[20] = 7;
+    let k_a = key_from_bytes(stem_repeat(1), 2);
+    let k_b = key_from_bytes(stem_repeat(1), 3);
+    let k_c = key_from_bytes(deep, 2);
+    let k_d = key_from_bytes(stem_repeat(9), 0);
+    for (i, k) in [k_a, k_b, k_c, k_d].iter().enumerate() {
+        tree.insert(*k, Value::from(vec![i as u8]));
+    }
+    let pre_root = tree.commit();
+
+    // The client only sees proofs for a, b and c; d stays outside the witness.
+    let witness: Vec<_> = [k_a, k_b, k_c].iter().map(|k| (*k, tree.prove_get(*k).unwrap())).collect();
+    let mut partial = PartialTree::from_witness(kzg.clone(), pre_root, &witness).expect("valid witness");
+    assert_eq!(partial.root().unwrap(), pre_root);
+    assert_eq!(partial.get(k_b), Some(Value::from(vec![1u8])));
+
+    for (k, v) in [(k_a, b"new-a".to_vec()), (k_b, b"new-b".to_vec()), (k_c, b"new-c".to_vec())] {
+        partial.insert(k, Value::from(v.clone())).unwrap();
+        tree.insert(k, Value::from(v));
+    }
+    assert_eq!(partial.get(k_b), Some(Value::from(&b"new-b"[..])));
+    assert_eq!(partial.root().unwrap(), tree.commit());
+}
+
+#[test]
+fn write_outside_witness_is_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
+
+    let proven = key_from_bytes(stem_repeat(1), 2);
+    let sibling = key_from_bytes(stem_repeat(1), 3);
+    let elsewhere = key_from_bytes(stem_repeat(4), 2);
+    tree.insert(proven, Value::from(vec![1]));
+    tree.insert(sibling, Value::from(vec![2]));
+    tree.insert(elsewhere, Value::from(vec![3]));
+    let root = tree.commit();
+
+    let witness = vec![(proven, tree.prove_get(proven).unwrap())];
+    let mut partial = PartialTree::from_witness(kzg, root, &witness).unwrap();
+
+    // Same stem but an unopened slot, and a different stem entirely
+    assert_eq!(partial.insert(sibling, Value::from(vec![9])), Err(PartialTreeError::NotWitnessed(sibling)));
+    assert_eq!(partial.insert(elsewhere, Value::from(vec![9])), Err(PartialTreeError::NotWitnessed(elsewhere)));
+    assert_eq!(partial.root().unwrap(), root);
+}
+
+#[test]
+fn tampered_witness_is_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
+
+    let key = key_from_bytes(stem_repeat(1), 2);
+    tree.insert(key, Value::from(vec![1, 2, 3]));
+    let root = tree.commit();
+
+    let mut proof = tree.prove_get(key).unwrap();
+    proof.value = Bytes::from_static(&[99, 2, 3]);
+    let result = PartialTree::from_witness(kzg, root, &[(key, proof)]);
+    assert!(matches!(result, Err(PartialTreeError::InvalidProof(k)) if k == key));
+}
+
+#[test]
+fn writes_are_rejected_without_update_at() {
+    let vc = NoUpdates(MockVc);
+    let mut tree = VerkleTree::<NoUpdates>::new(vc.clone());
+    let key = key_from_bytes(stem_repeat(1), 2);
+    tree.insert(key, Value::from(vec![1]));
+    let root = tree.commit();
+
+    let witness = vec![(key, tree.prove_get(key).unwrap())];
+    let mut partial = PartialTree::from_witness(vc, root, &witness).expect("reads only need verify_at");
+    assert_eq!(partial.root(), Ok(root));
+    partial.insert(key, Value::from(vec![2])).unwrap();
+    assert_eq!(partial.root(), Err(PartialTreeError::UpdateRejected));
+}
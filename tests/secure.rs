@@ -0,0 +1,46 @@
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{vc::verify_proof, KzgVc, SecureVerkleTree, Value};
+
+#[test]
+fn adversarial_prefixes_do_not_build_deep_chains() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = SecureVerkleTree::<KzgVc>::new(kzg.clone());
+
+    // Stems that would only diverge at the last stem byte if used directly.
+    let keys: Vec<[u8; 32]> = (0..4u8).map(|i| { let mut k = [0xAA; 32]; k[30] = i; k }).collect();
+    for (i, k) in keys.iter().enumerate() {
+        tree.insert(k, Value::from(vec![i as u8]));
+    }
+    let root = tree.commit();
+
+    for (i, k) in keys.iter().enumerate() {
+        assert_eq!(tree.get(k), Some(Value::from(vec![i as u8])));
+        let proof = tree.prove_get(k).expect("present key has a proof");
+        assert!(proof.steps.len() <= 3, "hashed stems should split near the root");
+        assert!(verify_proof(&kzg, &root, &proof, SecureVerkleTree::<KzgVc>::tree_key(k)));
+    }
+    assert_eq!(tree.get(b"missing"), None);
+}
+
+#[test]
+fn preimages_are_kept_only_on_request() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut plain = SecureVerkleTree::<KzgVc>::new(kzg.clone());
+    let mut keeping = SecureVerkleTree::<KzgVc>::with_preimages(kzg);
+
+    for key in [&b"alice"[..], b"bob", b"carol"] {
+        plain.insert(key, Value::from(key.to_vec()));
+        keeping.insert(key, Value::from(key.to_vec()));
+    }
+    assert_eq!(plain.commit(), keeping.commit());
+
+    assert!(plain.iter().all(|(_, original, _)| original.is_none()));
+    for (tree_key, original, value) in keeping.iter() {
+        let original = original.expect("preimage kept");
+        assert_eq!(SecureVerkleTree::<KzgVc>::tree_key(original), tree_key);
+        assert_eq!(value.0, original);
+    }
+    assert_eq!(keeping.iter().count(), 3);
+}
@@ -0,0 +1,161 @@
+use bytes::Bytes;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{
+    snap::{ProvenNode, SnapError, SnapImporter},
+    MockVc, Value, VerkleTree,
+};
+
+// A committed tree whose stems share prefixes and hold a few slots each. Chunks open every slot
+// and child of their range, so these tests use MockVc to keep that cheap.
+fn source_tree(rng: &mut StdRng) -> VerkleTree<MockVc> {
+    let mut t = VerkleTree::<MockVc>::new(MockVc);
+    for _ in 0..40 {
+        let mut k: [u8; 32] = rng.gen();
+        k[0] = rng.gen_range(0..4);
+        k[31] = rng.gen_range(0..3);
+        t.insert(k, Value::from(vec![rng.gen(); rng.gen_range(1..24)]));
+    }
+    t.commit();
+    t
+}
+
+#[test]
+fn iter_from_matches_filtered_iter() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let source = source_tree(&mut rng);
+
+    let keys: Vec<_> = source.iter().map(|(k, _)| k).collect();
+    let mut starts = vec![[0u8; 32], [0xFF; 32], keys[7]];
+    let mut between = keys[12];
+    between[31] = between[31].wrapping_add(1);
+    starts.push(between);
+    for _ in 0..8 {
+        starts.push(rng.gen());
+    }
+
+    for start in starts {
+        let got: Vec<_> = source.iter_from(start).map(|(k, _)| k).collect();
+        let want: Vec<_> = keys.iter().copied().filter(|k| *k >= start).collect();
+        assert_eq!(got, want);
+    }
+}
+
+#[test]
+fn chunked_export_imports_to_the_same_root() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut source = source_tree(&mut rng);
+    let root = source.commit();
+
+    let mut importer = SnapImporter::new(MockVc, root);
+    let (mut start, mut chunks, mut imported) = (Some([0u8; 32]), 0, 0);
+    while let Some(from) = start {
+        let chunk = source.export_range(from, 300).expect("tree is committed");
+        let added = importer.import_chunk(&chunk).expect("honest chunk verifies");
+        assert!(chunk.entries().map(|(k, _)| k).eq(source.iter_from(from).map(|(k, _)| k).take(added)));
+        imported += added;
+        chunks += 1;
+        start = chunk.next;
+    }
+
+    assert!(chunks > 1);
+    assert_eq!(imported, source.iter().count());
+    let synced = importer.finish().expect("all entries imported");
+    assert!(synced.diff(&source).expect("both committed").is_empty());
+}
+
+#[test]
+fn forged_chunks_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut source = source_tree(&mut rng);
+    let root = source.commit();
+    let mut importer = SnapImporter::new(MockVc, root);
+
+    let mut chunk = source.export_range([0u8; 32], 200).expect("tree is committed");
+    let value = chunk.nodes.iter_mut().find_map(|node| match node {
+        ProvenNode::Extension { slots, .. } => slots.iter_mut().find_map(|(_, value, _)| value.as_mut()),
+        ProvenNode::Internal { .. } => None,
+    });
+    *value.expect("chunk holds a value") = Bytes::from_static(b"forged");
+    assert_eq!(importer.import_chunk(&chunk), Err(SnapError::InvalidProof));
+
+    // Leaving out a slot or a child of the range could hide entries
+    let mut chunk = source.export_range([0u8; 32], 200).expect("tree is committed");
+    let slots = chunk.nodes.iter_mut().find_map(|node| match node {
+        ProvenNode::Extension { slots, .. } => Some(slots),
+        ProvenNode::Internal { .. } => None,
+    });
+    slots.expect("chunk holds a stem").remove(0);
+    assert_eq!(importer.import_chunk(&chunk), Err(SnapError::Gap));
+
+    let mut chunk = source.export_range([0u8; 32], 200).expect("tree is committed");
+    let ProvenNode::Internal { children, .. } = &mut chunk.nodes[0] else { panic!("root is Internal") };
+    children.pop();
+    assert_eq!(importer.import_chunk(&chunk), Err(SnapError::Gap));
+
+    let mut chunk = source.export_range([0u8; 32], 200).expect("tree is committed");
+    chunk.next = Some([0u8; 32]);
+    assert_eq!(importer.import_chunk(&chunk), Err(SnapError::InvalidRange));
+
+    // A chunk from another state does not verify against the expected root
+    let mut other = source_tree(&mut rng);
+    other.commit();
+    assert_eq!(importer.import_chunk(&other.export_range([0u8; 32], 200).expect("tree is committed")), Err(SnapError::InvalidProof));
+    assert_eq!(importer.missing(), vec![([0u8; 32], None)]);
+}
+
+#[test]
+fn healing_fills_skipped_chunks() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut source = source_tree(&mut rng);
+    let root = source.commit();
+
+    let mut importer = SnapImporter::new(MockVc, root);
+    let (mut start, mut skip) = (Some([0u8; 32]), false);
+    while let Some(from) = start {
+        let chunk = source.export_range(from, 300).expect("tree is committed");
+        // Drop every other chunk, as if the peer serving it went away
+        if !skip {
+            importer.import_chunk(&chunk).expect("honest chunk verifies");
+        }
+        skip = !skip;
+        start = chunk.next;
+    }
+    assert!(importer.missing().len() > 1);
+
+    // The importer asks for what it lacks; the peer only serves chunks
+    let mut requests = 0;
+    let healed = importer
+        .heal(|start| {
+            requests += 1;
+            source.export_range(start, 300)
+        })
+        .expect("peer is at the expected root");
+    assert!(healed > 0 && requests > 1);
+    assert!(importer.missing().is_empty());
+    let synced = importer.finish().expect("healed");
+    assert!(synced.diff(&source).expect("both committed").is_empty());
+}
+
+#[test]
+fn unhealed_import_is_incomplete() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut source = source_tree(&mut rng);
+    let root = source.commit();
+
+    let mut importer = SnapImporter::new(MockVc, root);
+    let chunk = source.export_range([0u8; 32], 300).expect("tree is committed");
+    importer.import_chunk(&chunk).expect("honest chunk verifies");
+    assert_eq!(importer.missing(), vec![(chunk.next.expect("more chunks follow"), None)]);
+    assert!(matches!(importer.finish(), Err(SnapError::Incomplete)));
+}
+
+#[test]
+fn uncommitted_tree_is_not_exported() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let mut source = source_tree(&mut rng);
+    source.insert([0u8; 32], Value::from(vec![1]));
+    assert!(matches!(source.export_range([0u8; 32], 300), Err(SnapError::Uncommitted)));
+
+    source.commit();
+    assert!(source.export_range([0u8; 32], 300).is_ok());
+}
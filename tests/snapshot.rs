@@ -0,0 +1,92 @@
+use std::io;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{
+    hasher::{Blake3Hasher, Sha256Hasher},
+    snapshot::SnapshotError,
+    vc::verify_proof,
+    KzgVc, Value, VerkleTree,
+};
+
+fn random_tree(kzg: &KzgVc<'static>, rng: &mut StdRng) -> VerkleTree<KzgVc<'static>> {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for _ in 0..30 {
+        let mut k: [u8; 32] = rng.gen();
+        // Shared prefixes, so the snapshot holds Internal chains as well as siblings
+        k[0] = rng.gen_range(0..3);
+        k[1] = rng.gen_range(0..2);
+        k[31] = rng.gen_range(0..4);
+        t.insert(k, Value::from(vec![rng.gen(); rng.gen_range(0..20)]));
+    }
+    t
+}
+
+#[test]
+fn snapshot_roundtrips_with_and_without_caches() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = random_tree(&kzg, &mut rng);
+
+    let mut with_caches = Vec::new();
+    let root = tree.write_snapshot(&mut with_caches, true).unwrap();
+    let mut without = Vec::new();
+    assert_eq!(tree.write_snapshot(&mut without, false).unwrap(), root);
+    assert!(without.len() < with_caches.len());
+
+    for bytes in [&with_caches, &without] {
+        let loaded = VerkleTree::<KzgVc>::read_snapshot(&bytes[..], kzg.clone()).expect("valid snapshot");
+        assert!(loaded.is_canonical());
+        assert_eq!(loaded.iter().collect::<Vec<_>>(), tree.iter().collect::<Vec<_>>());
+        // Proofs come straight from the loaded caches, without a commit
+        for (k, v) in tree.iter().step_by(5) {
+            let proof = loaded.prove_get(k).expect("stored key");
+            assert_eq!(proof.value, v.0);
+            assert!(verify_proof(&kzg, &root, &proof, k));
+        }
+    }
+
+    let mut empty = VerkleTree::<KzgVc>::new(kzg.clone());
+    let mut bytes = Vec::new();
+    empty.write_snapshot(&mut bytes, true).unwrap();
+    let mut loaded = VerkleTree::<KzgVc>::read_snapshot(&bytes[..], kzg.clone()).expect("empty snapshot");
+    assert_eq!(loaded.iter().count(), 0);
+    assert_eq!(loaded.commit(), empty.commit());
+}
+
+#[test]
+fn incompatible_or_damaged_snapshots_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = random_tree(&kzg, &mut rng);
+    let mut bytes = Vec::new();
+    tree.write_snapshot(&mut bytes, true).unwrap();
+
+    let read = |bytes: &[u8]| VerkleTree::<KzgVc>::read_snapshot(bytes, kzg.clone());
+    assert!(matches!(VerkleTree::<KzgVc, Sha256Hasher>::read_snapshot(&bytes[..], kzg.clone()), Err(SnapshotError::Incompatible("hash function"))));
+    assert!(matches!(VerkleTree::<KzgVc<'static, 16>, Blake3Hasher, 16>::read_snapshot(&bytes[..], KzgVc::setup(&mut rng).unwrap()), Err(SnapshotError::Incompatible("width"))));
+    assert!(matches!(read(&bytes[..bytes.len() - 1]), Err(SnapshotError::Io(_))));
+    assert!(matches!(read(b"not a snapshot"), Err(SnapshotError::Format(_))));
+    // A corrupt length fails on the missing bytes instead of allocating them
+    let mut huge = bytes.clone();
+    huge[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(read(&huge), Err(SnapshotError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof));
+
+    // A slot value changed after the root was recorded
+    tree.insert([0x77; 32], Value::from(&b"tamper-me"[..]));
+    let mut tampered = Vec::new();
+    tree.write_snapshot(&mut tampered, false).unwrap();
+    let at = tampered.windows(9).position(|w| w == b"tamper-me").expect("value is stored");
+    tampered[at] ^= 1;
+    assert!(matches!(read(&tampered), Err(SnapshotError::RootMismatch)));
+
+    // Caches are not verified, so the same change below the root loads until the next commit
+    let mut tampered = Vec::new();
+    let root = tree.write_snapshot(&mut tampered, true).unwrap();
+    let at = tampered.windows(9).position(|w| w == b"tamper-me").expect("value is stored");
+    tampered[at] ^= 1;
+    assert_ne!(read(&tampered).expect("cached digests are trusted").commit(), root);
+
+    // Loaded with another setup, the cached root does not match
+    let other = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    assert!(matches!(VerkleTree::<KzgVc>::read_snapshot(&bytes[..], other), Err(SnapshotError::RootMismatch)));
+}
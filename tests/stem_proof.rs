@@ -0,0 +1,113 @@
+use bytes::Bytes;
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{
+    vc::{verify_stem_proof, Step},
+    KzgVc, Value, VerkleTree,
+};
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_repeat(b: u8) -> [u8; 31] {
+    let mut s = [0u8; 31];
+    s.fill(b);
+    s
+}
+
+// An "account" stem with header fields at suffixes 0..=2 next to two unrelated stems.
+fn account_tree(kzg: &KzgVc<'static>) -> VerkleTree<KzgVc<'static>> {
+    let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
+    let account = stem_repeat(0x11);
+    for suf in 0..=2u8 {
+        tree.insert(key_from_bytes(account, suf), Value::from(vec![suf; 8]));
+    }
+    let mut near = account;
+    near[20] = 0x12;
+    tree.insert(key_from_bytes(near, 0), Value::from(&b"near"[..]));
+    tree.insert(key_from_bytes(stem_repeat(0x99), 0), Value::from(&b"far"[..]));
+    tree
+}
+
+#[test]
+fn stem_proof_covers_present_and_empty_slots() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = account_tree(&kzg);
+    let root = tree.commit();
+    let account = stem_repeat(0x11);
+
+    let proof = tree.prove_stem(key_from_bytes(account, 0), &[2, 0, 5, 1, 2]).expect("stem is stored");
+    let proven = verify_stem_proof(&kzg, &root, &proof, key_from_bytes(account, 0)).expect("valid proof");
+    let expected = [(0, Some(Value::from(vec![0u8; 8]))), (1, Some(Value::from(vec![1u8; 8]))), (2, Some(Value::from(vec![2u8; 8]))), (5, None)];
+    assert_eq!(proven.into_iter().collect::<Vec<_>>(), expected);
+
+    // The shared path is the one a single-key proof takes
+    let single = tree.prove_get(key_from_bytes(account, 0)).unwrap();
+    let hops = |steps: &[Step<KzgVc<'static>>]| -> Vec<_> {
+        steps.iter().filter_map(|s| match s {
+            Step::Internal { parent_commit, index, .. } => Some((*parent_commit, *index)),
+            Step::Extension { .. } => None,
+        }).collect()
+    };
+    assert_eq!(proof.steps.len() + 1, single.steps.len());
+    assert_eq!(hops(&proof.steps), hops(&single.steps));
+    assert!(matches!(single.steps.last(), Some(Step::Extension { ext_commit, .. }) if *ext_commit == proof.ext_commit));
+}
+
+#[test]
+fn tampered_stem_proofs_are_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = account_tree(&kzg);
+    let root = tree.commit();
+    let account = stem_repeat(0x11);
+    let proof = tree.prove_stem(key_from_bytes(account, 0), &[0, 5]).unwrap();
+
+    // Another stem's slots are not covered by this Extension
+    let mut other = account;
+    other[20] = 0x12;
+    assert!(verify_stem_proof(&kzg, &root, &proof, key_from_bytes(other, 0)).is_none());
+
+    // A changed value, a claimed value for an empty slot and a hidden value all fail
+    let mut changed = proof.clone();
+    changed.slots[0].1 = Some(Bytes::from_static(b"forged"));
+    assert!(verify_stem_proof(&kzg, &root, &changed, key_from_bytes(account, 0)).is_none());
+
+    let mut filled = proof.clone();
+    filled.slots[1].1 = Some(Bytes::from_static(b"forged"));
+    assert!(verify_stem_proof(&kzg, &root, &filled, key_from_bytes(account, 0)).is_none());
+
+    let mut hidden = proof.clone();
+    hidden.slots[0].1 = None;
+    assert!(verify_stem_proof(&kzg, &root, &hidden, key_from_bytes(account, 0)).is_none());
+
+    // Repeating a slot is rejected
+    let mut repeated = proof.clone();
+    repeated.slots.push(proof.slots[1].clone());
+    assert!(verify_stem_proof(&kzg, &root, &repeated, key_from_bytes(account, 0)).is_none());
+
+    // Against a different root
+    tree.insert(key_from_bytes(account, 3), Value::from(vec![3]));
+    let new_root = tree.commit();
+    assert!(verify_stem_proof(&kzg, &new_root, &proof, key_from_bytes(account, 0)).is_none());
+}
+
+#[test]
+fn prove_stem_rejects_missing_stems_and_wide_suffixes() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = account_tree(&kzg);
+    tree.commit();
+
+    assert!(tree.prove_stem(key_from_bytes(stem_repeat(0x42), 0), &[0]).is_none());
+
+    let mut narrow = VerkleTree::<KzgVc<'static, 16>, verkle::hasher::Blake3Hasher, 16>::new(KzgVc::setup(&mut rng).unwrap());
+    narrow.insert(key_from_bytes(stem_repeat(0x11), 0), Value::from(vec![1]));
+    narrow.commit();
+    assert!(narrow.prove_stem(key_from_bytes(stem_repeat(0x11), 0), &[0, 16]).is_none());
+    assert!(narrow.prove_stem(key_from_bytes(stem_repeat(0x11), 0), &[0, 15]).is_some());
+}
@@ -25,8 +25,8 @@ fn proof_accepts_altered_trailing_stem_bytes_bug() {
     let key1 = make_key(stem1, 5);
     let key2 = make_key(stem2, 6); // different suffix just to populate another slot
 
-    tree.insert(key1, Value(vec![1,2,3]));
-    tree.insert(key2, Value(vec![4,5,6]));
+    tree.insert(key1, Value::from(vec![1,2,3]));
+    tree.insert(key2, Value::from(vec![4,5,6]));
 
     let root = tree.commit();
 
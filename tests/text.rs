@@ -0,0 +1,87 @@
+use std::io::Cursor;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{
+    text::{ImportError, TextFormat},
+    KzgVc, Value, VerkleTree,
+};
+
+fn random_tree(kzg: &KzgVc<'static>, rng: &mut StdRng) -> VerkleTree<KzgVc<'static>> {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for _ in 0..30 {
+        let mut k: [u8; 32] = rng.gen();
+        k[0] = rng.gen_range(0..4);
+        let len = rng.gen_range(0..40);
+        t.insert(k, Value::from((0..len).map(|_| rng.gen()).collect::<Vec<u8>>()));
+    }
+    t
+}
+
+#[test]
+fn export_then_import_round_trips() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut source = random_tree(&kzg, &mut rng);
+
+    for format in [TextFormat::Csv, TextFormat::JsonLines] {
+        let mut out = Vec::new();
+        let root = source.export_text(&mut out, format).expect("write to memory");
+
+        let text = String::from_utf8(out.clone()).unwrap();
+        let keys: Vec<_> = source.iter().map(|(k, _)| k).collect();
+        let lines: Vec<_> = text.lines().collect();
+        let body = if format == TextFormat::Csv { &lines[1..lines.len() - 1] } else { &lines[..lines.len() - 1] };
+        assert_eq!(body.len(), keys.len());
+        assert!(lines.last().unwrap().contains("root"));
+
+        let mut imported = VerkleTree::<KzgVc>::new(kzg.clone());
+        assert_eq!(imported.import_text(Cursor::new(out), format).expect("valid export"), keys.len());
+        assert_eq!(imported.commit(), root);
+        assert!(imported.diff(&source).expect("both committed").is_empty());
+    }
+}
+
+#[test]
+fn import_checks_the_trailer_root() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut source = random_tree(&kzg, &mut rng);
+
+    let mut out = Vec::new();
+    source.export_text(&mut out, TextFormat::Csv).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    // Drop one entry: the remaining entries no longer commit to the trailer
+    let mut lines: Vec<_> = text.lines().collect();
+    lines.remove(3);
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    assert!(matches!(t.import_text(Cursor::new(lines.join("\n")), TextFormat::Csv), Err(ImportError::RootMismatch)));
+
+    // Without a trailer there is nothing to check against
+    let entries = &text.lines().collect::<Vec<_>>()[..lines.len()];
+    let mut t = VerkleTree::<KzgVc>::new(kzg);
+    assert_eq!(t.import_text(Cursor::new(entries.join("\n")), TextFormat::Csv).unwrap(), entries.len() - 1);
+}
+
+#[test]
+fn malformed_lines_report_their_line_number() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let key = format!("0x{}", "01".repeat(32));
+
+    let cases = [
+        (TextFormat::Csv, format!("key,value\n{key},0x00\n\n{key}\n"), 4),
+        (TextFormat::Csv, format!("{key},0xzz\n"), 1),
+        (TextFormat::Csv, "0x0102,0x00\n".to_string(), 1),
+        (TextFormat::Csv, format!("root,0x00\n{key},0x00\n"), 2),
+        (TextFormat::JsonLines, format!("{{\"key\":\"{key}\",\"value\":\"0x01\"}}\n{{\"key\":\"{key}\"}}\n"), 2),
+        (TextFormat::JsonLines, "not json\n".to_string(), 1),
+    ];
+    for (format, input, expected_line) in cases {
+        let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+        match t.import_text(Cursor::new(input.clone()), format) {
+            Err(ImportError::Parse { line, .. }) => assert_eq!(line, expected_line, "{input:?}"),
+            other => panic!("expected a parse error for {input:?}, got {other:?}"),
+        }
+    }
+}
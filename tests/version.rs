@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{vc::verify_proof, KzgVc, Value, VerkleTree};
+
+fn random_key(rng: &mut StdRng) -> [u8; 32] {
+    let mut k: [u8; 32] = rng.gen();
+    // Few distinct prefixes, so later versions split Extensions shared with earlier ones
+    k[0] = rng.gen_range(0..3);
+    k[1] = rng.gen_range(0..3);
+    k[31] = rng.gen_range(0..4);
+    k
+}
+
+fn fresh_root(kzg: &KzgVc<'static>, entries: &BTreeMap<[u8; 32], Vec<u8>>) -> <KzgVc<'static> as verkle::vc::VectorCommitment>::Commitment {
+    let mut t = VerkleTree::<KzgVc>::new(kzg.clone());
+    for (k, v) in entries {
+        t.insert(*k, Value::from(v.clone()));
+    }
+    t.commit()
+}
+
+#[test]
+fn historical_reads_and_proofs_match_each_version() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
+
+    let mut model = BTreeMap::new();
+    let mut history = Vec::new();
+    for version in 1..=4u64 {
+        for _ in 0..12 {
+            // Overwrite earlier keys as well as adding new ones
+            let key = match model.keys().nth(rng.gen_range(0..model.len().max(1))) {
+                Some(k) if rng.gen_bool(0.3) => *k,
+                _ => random_key(&mut rng),
+            };
+            let value = vec![version as u8; rng.gen_range(1..8)];
+            tree.insert(key, Value::from(value.clone()));
+            model.insert(key, value);
+        }
+        let root = tree.commit_version(version);
+        assert_eq!(root, fresh_root(&kzg, &model));
+        history.push((version, root, model.clone()));
+    }
+
+    for (version, root, entries) in &history {
+        assert_eq!(tree.root_at(*version), Some(*root));
+        for (k, v) in entries {
+            assert_eq!(tree.get_at(*version, *k), Some(Value::from(v.clone())));
+            let proof = tree.prove_get_at(*version, *k).expect("key was set in this version");
+            assert_eq!(proof.value, &v[..]);
+            assert!(verify_proof(&kzg, root, &proof, *k));
+        }
+        // Keys added later are absent from earlier versions
+        for k in model.keys().filter(|k| !entries.contains_key(*k)) {
+            assert_eq!(tree.get_at(*version, *k), None);
+            assert!(tree.prove_get_at(*version, *k).is_none());
+        }
+    }
+
+    // Writes after the last version leave the recorded ones untouched
+    let (k, _) = history[0].2.iter().next().unwrap();
+    tree.insert(*k, Value::from(&b"latest"[..]));
+    assert_eq!(tree.get_at(1, *k), Some(Value::from(history[0].2[k].clone())));
+    assert_eq!(tree.get(*k), Some(Value::from(&b"latest"[..])));
+    model.insert(*k, b"latest".to_vec());
+    assert_eq!(tree.commit(), fresh_root(&kzg, &model));
+}
+
+#[test]
+fn pruning_keeps_the_retention_window() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg.clone());
+
+    let key = random_key(&mut rng);
+    for version in [1u64, 2, 3, 5, 6] {
+        tree.insert(key, Value::from(vec![version as u8]));
+        tree.commit_version(version);
+    }
+
+    // Versions 4..=6 fall inside a window of three
+    assert_eq!(tree.prune_versions(3), 3);
+    assert_eq!(tree.versions().collect::<Vec<_>>(), vec![5, 6]);
+    assert_eq!(tree.get_at(3, key), None);
+    assert!(tree.prove_get_at(3, key).is_none());
+    assert_eq!(tree.get_at(5, key), Some(Value::from(vec![5u8])));
+    assert_eq!(tree.prune_versions(3), 0);
+}
+
+#[test]
+#[should_panic(expected = "is not after the latest version")]
+fn versions_must_increase() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc>::new(kzg);
+    tree.commit_version(2);
+    tree.commit_version(2);
+}
@@ -0,0 +1,250 @@
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    io,
+    path::PathBuf,
+    rc::Rc,
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+use verkle::{
+    hasher::Blake3Hasher,
+    wal::{WalError, WalLog},
+    KzgVc, Value, VerkleTree, WalTree,
+};
+
+// The crash test recovers once per byte of the log, so it runs on a narrow tree for speed.
+type NarrowVc = KzgVc<'static, 16>;
+type NarrowWal = WalTree<NarrowVc, Blake3Hasher, 16>;
+
+// A key and the value to write there, or None to remove it.
+type Write = ([u8; 32], Option<&'static [u8]>);
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+fn stem_repeat(b: u8) -> [u8; 31] {
+    let mut s = [0u8; 31];
+    s.fill(b);
+    s
+}
+
+// A fresh log path for this test process.
+fn log_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("verkle-wal-{}-{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// An in-memory log whose writes fail after `budget` more bytes, having written those bytes.
+#[derive(Clone, Default)]
+struct FlakyLog {
+    bytes: Rc<RefCell<Vec<u8>>>,
+    budget: Rc<Cell<Option<usize>>>, // None never fails
+}
+
+impl io::Read for FlakyLog {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Ok(0) // only read when opening, and the tests open empty logs
+    }
+}
+
+impl io::Write for FlakyLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = match self.budget.get() {
+            Some(0) => return Err(io::Error::other("disk full")),
+            Some(budget) => budget.min(buf.len()),
+            None => buf.len(),
+        };
+        self.budget.set(self.budget.get().map(|budget| budget - len));
+        self.bytes.borrow_mut().extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl WalLog for FlakyLog {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        self.bytes.borrow_mut().truncate(len as usize);
+        Ok(())
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn reopen_discards_the_uncommitted_batch() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let path = log_path("reopen");
+    let (k_a, k_b, k_c) = (key_from_bytes(stem_repeat(1), 0), key_from_bytes(stem_repeat(1), 1), key_from_bytes(stem_repeat(2), 0));
+
+    let mut wal = WalTree::<KzgVc>::open(kzg.clone(), &path).expect("new log");
+    wal.insert(k_a, Value::from(&b"a"[..])).unwrap();
+    wal.insert(k_b, Value::from(&b"b"[..])).unwrap();
+    wal.insert(k_c, Value::from(&b"c"[..])).unwrap();
+    assert_eq!(wal.remove(k_b).unwrap(), Some(Value::from(&b"b"[..])));
+    let root = wal.commit().unwrap();
+    wal.insert(k_b, Value::from(&b"lost"[..])).unwrap();
+    wal.remove(k_c).unwrap();
+    drop(wal);
+
+    let mut wal = WalTree::<KzgVc>::open(kzg.clone(), &path).expect("reopen");
+    assert_eq!(wal.get(k_a), Some(Value::from(&b"a"[..])));
+    assert_eq!(wal.get(k_b), None);
+    assert_eq!(wal.get(k_c), Some(Value::from(&b"c"[..])));
+
+    // Writes after recovery follow the last marker, not the discarded batch
+    wal.insert(k_b, Value::from(&b"kept"[..])).unwrap();
+    let root2 = wal.commit().unwrap();
+    assert_ne!(root, root2);
+    drop(wal);
+    let mut wal = WalTree::<KzgVc>::open(kzg.clone(), &path).expect("reopen");
+    assert_eq!(wal.get(k_b), Some(Value::from(&b"kept"[..])));
+    assert_eq!(wal.commit().unwrap(), root2);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn crash_at_every_offset_recovers_the_last_commit() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = NarrowVc::setup(&mut rng).expect("KZG setup should not fail");
+    let path = log_path("crash");
+    let mut deep = stem_repeat(3);
+    deep[30] = 4;
+
+    let mut wal = NarrowWal::open(kzg.clone(), &path).expect("new log");
+    let mut empty = VerkleTree::<NarrowVc, Blake3Hasher, 16>::new(kzg.clone());
+    // (log length, root) after each commit
+    let mut commits = vec![(0, empty.commit())];
+    let batches: [&[Write]; 3] = [
+        &[(key_from_bytes(stem_repeat(3), 0), Some(b"x")), (key_from_bytes(deep, 7), Some(b"yy"))],
+        &[(key_from_bytes(stem_repeat(3), 0), None), (key_from_bytes(stem_repeat(9), 2), Some(b""))],
+        &[(key_from_bytes(deep, 7), Some(b"zzz"))],
+    ];
+    for batch in batches {
+        for (key, value) in batch {
+            match value {
+                Some(v) => wal.insert(*key, Value::from(v.to_vec())).unwrap(),
+                None => assert!(wal.remove(*key).unwrap().is_some()),
+            }
+        }
+        let root = wal.commit().unwrap();
+        commits.push((fs::metadata(&path).unwrap().len() as usize, root));
+    }
+    // A trailing partial batch
+    wal.insert(key_from_bytes(stem_repeat(5), 1), Value::from(&b"partial"[..])).unwrap();
+    drop(wal);
+    let log = fs::read(&path).unwrap();
+
+    for cut in 0..=log.len() {
+        let (mut tree, committed) = NarrowWal::recover(kzg.clone(), &log[..cut]).expect("truncated log recovers");
+        let &(len, root) = commits.iter().rev().find(|(len, _)| *len <= cut).unwrap();
+        assert_eq!(committed, len, "cut at {cut}");
+        assert_eq!(tree.commit(), root, "cut at {cut}");
+    }
+
+    // Reopening a torn log truncates it to the last marker
+    fs::write(&path, &log[..log.len() - 3]).unwrap();
+    let mut wal = NarrowWal::open(kzg.clone(), &path).expect("reopen");
+    assert_eq!(fs::metadata(&path).unwrap().len() as usize, commits[3].0);
+    assert_eq!(wal.commit().unwrap(), commits[3].1);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn damaged_early_batch_is_not_truncated() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = NarrowVc::setup(&mut rng).expect("KZG setup should not fail");
+    let path = log_path("damaged");
+
+    let mut wal = NarrowWal::open(kzg.clone(), &path).expect("new log");
+    wal.insert(key_from_bytes(stem_repeat(1), 0), Value::from(&b"first"[..])).unwrap();
+    wal.commit().unwrap();
+    wal.insert(key_from_bytes(stem_repeat(2), 0), Value::from(&b"second"[..])).unwrap();
+    wal.commit().unwrap();
+    drop(wal);
+
+    // Flip a byte of the first value, so the first record no longer matches its checksum
+    let mut log = fs::read(&path).unwrap();
+    log[40] ^= 0xff;
+    fs::write(&path, &log).unwrap();
+
+    assert!(matches!(NarrowWal::recover(kzg.clone(), &log), Err(WalError::Corrupt { offset: 0 })));
+    assert!(matches!(NarrowWal::open(kzg, &path), Err(WalError::Corrupt { offset: 0 })));
+    // The later commit is still on disk
+    assert_eq!(fs::read(&path).unwrap(), log);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn log_from_another_setup_is_rejected() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let other = KzgVc::setup(&mut rng).expect("KZG setup should not fail");
+    let path = log_path("setup");
+
+    let mut wal = WalTree::<KzgVc>::open(kzg, &path).expect("new log");
+    wal.insert(key_from_bytes(stem_repeat(1), 0), Value::from(&b"a"[..])).unwrap();
+    wal.commit().unwrap();
+    drop(wal);
+
+    assert!(matches!(WalTree::<KzgVc>::open(other, &path), Err(WalError::RootMismatch { .. })));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_append_leaves_no_torn_record() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = NarrowVc::setup(&mut rng).expect("KZG setup should not fail");
+    let log = FlakyLog::default();
+    let (k_a, k_b, k_c) = (key_from_bytes(stem_repeat(1), 0), key_from_bytes(stem_repeat(2), 0), key_from_bytes(stem_repeat(3), 0));
+
+    let mut wal = WalTree::<NarrowVc, Blake3Hasher, 16, FlakyLog>::with_log(kzg.clone(), log.clone()).expect("new log");
+    wal.insert(k_a, Value::from(&b"a"[..])).unwrap();
+    let before = log.bytes.borrow().len();
+    log.budget.set(Some(10));
+    assert!(wal.insert(k_b, Value::from(&b"lost"[..])).is_err());
+    assert_eq!(log.bytes.borrow().len(), before);
+    assert_eq!(wal.get(k_b), None);
+
+    // Later records must still replay, which a torn record before them would prevent
+    log.budget.set(None);
+    wal.insert(k_c, Value::from(&b"c"[..])).unwrap();
+    let root = wal.commit().unwrap();
+    let (mut tree, committed) = NarrowWal::recover(kzg, &log.bytes.borrow()).expect("log replays");
+    assert_eq!(committed, log.bytes.borrow().len());
+    assert_eq!(tree.get(k_b), None);
+    assert_eq!(tree.get(k_c), Some(Value::from(&b"c"[..])));
+    assert_eq!(tree.commit(), root);
+}
+
+#[test]
+fn wide_last_byte_replays_in_a_narrow_tree() {
+    let mut rng = StdRng::seed_from_u64(0xDEADBEEFCAFEBABE);
+    let kzg = NarrowVc::setup(&mut rng).expect("KZG setup should not fail");
+    let log = FlakyLog::default();
+    // Only the low four bits are the suffix, so these are two stems
+    let (k_a, k_b) = (key_from_bytes(stem_repeat(1), 0x0f), key_from_bytes(stem_repeat(1), 0xff));
+
+    let mut wal = WalTree::<NarrowVc, Blake3Hasher, 16, FlakyLog>::with_log(kzg.clone(), log.clone()).expect("new log");
+    wal.insert(k_a, Value::from(&b"a"[..])).unwrap();
+    wal.insert(k_b, Value::from(&b"b"[..])).unwrap();
+    let root = wal.commit().unwrap();
+    let (mut tree, _) = NarrowWal::recover(kzg, &log.bytes.borrow()).expect("log replays");
+    assert_eq!(tree.get(k_a), Some(Value::from(&b"a"[..])));
+    assert_eq!(tree.get(k_b), Some(Value::from(&b"b"[..])));
+    assert_eq!(tree.commit(), root);
+}
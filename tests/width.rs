@@ -0,0 +1,71 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use verkle::{flat::FlatTree, hasher::Blake3Hasher, vc::verify_proof, KzgVc, Value, VerkleTree};
+
+fn key_from_bytes(stem: [u8; 31], suffix: u8) -> [u8; 32] {
+    let mut k = [0u8; 32];
+    k[..31].copy_from_slice(&stem);
+    k[31] = suffix;
+    k
+}
+
+// Inserts stems that differ in the high and low nibble of several bytes, so a width-16 tree
+// splits them mid-byte, then checks every value and proof against the root, and again after a
+// snapshot and a flat file round-trip.
+fn roundtrip<const W: usize>(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let kzg = KzgVc::<'static, W>::setup(&mut rng).expect("KZG setup should not fail");
+    let mut tree = VerkleTree::<KzgVc<'static, W>, Blake3Hasher, W>::new(kzg.clone());
+
+    let mut keys = Vec::new();
+    for (byte, bits) in [(0, 0x10), (0, 0x01), (7, 0x80), (30, 0x01), (30, 0x02)] {
+        let mut stem = [0x33u8; 31];
+        stem[byte] ^= bits;
+        keys.push(key_from_bytes(stem, rng.gen_range(0..W) as u8));
+    }
+    keys.push(key_from_bytes([0x33u8; 31], (W - 1) as u8));
+    // In a narrow tree the high bits of the last byte belong to the stem
+    if W < 256 {
+        keys.push(key_from_bytes([0x33u8; 31], 0xff));
+        keys.push(key_from_bytes([0x33u8; 31], 0xf0));
+    }
+
+    for (i, k) in keys.iter().enumerate() {
+        tree.insert(*k, Value::from(vec![i as u8; 3]));
+    }
+    assert!(tree.is_canonical());
+    let root = tree.commit();
+
+    for (i, k) in keys.iter().enumerate() {
+        assert_eq!(tree.get(*k), Some(Value::from(vec![i as u8; 3])));
+        let proof = tree.prove_get(*k).expect("present key has a proof");
+        assert_eq!(proof.value, vec![i as u8; 3]);
+        assert!(verify_proof(&kzg, &root, &proof, *k));
+    }
+
+    let mut bytes = Vec::new();
+    tree.write_snapshot(&mut bytes, false).expect("write to a Vec");
+    let read = VerkleTree::<KzgVc<'static, W>, Blake3Hasher, W>::read_snapshot(&bytes[..], kzg.clone()).expect("valid snapshot");
+    let mut flat = Vec::new();
+    tree.write_flat(&mut flat).expect("write to a Vec");
+    let flat = FlatTree::<_, KzgVc<'static, W>, Blake3Hasher, W>::new(&flat[..], kzg.clone()).expect("valid flat file");
+    assert_eq!(flat.root(), root);
+    for (i, k) in keys.iter().enumerate() {
+        assert_eq!(read.get(*k), Some(Value::from(vec![i as u8; 3])));
+        assert_eq!(flat.get(*k), Some(Value::from(vec![i as u8; 3])));
+    }
+}
+
+#[test]
+fn width_16_roundtrip() {
+    roundtrip::<16>(16);
+}
+
+#[test]
+fn width_64_roundtrip() {
+    roundtrip::<64>(64);
+}
+
+#[test]
+fn width_256_roundtrip() {
+    roundtrip::<256>(256);
+}